@@ -1,94 +1,987 @@
-use crate::custom_table::TableState;
-use crate::popup::FileNamePopup;
+use crate::buffer::QueryBuffer;
+use crate::chart::{ChartConfig, ChartData};
+use crate::config::Config;
+use crate::diagnostic::{self, Diagnostic};
+use crate::diff::Diff;
+use crate::dot::{self, DotCommand, Piece};
+use crate::editing;
+use crate::editor::{EditorAction, EditorMode, VimMode};
+use crate::format::format_sql;
+use crate::keymap::Action;
+use crate::library::{self, SavedQuery};
+use crate::notify::{Level, Notifications};
+use crate::output;
+use crate::params::{self, Parameter};
+use crate::plan::{analyzed_query, Plan};
+use crate::popup::{FileNamePopup, FormPopup, InputPopup, PickerItem, PickerPopup};
+use crate::profile::{Profile, ProfileState};
+use crate::results::ResultTab;
+use crate::search::SearchBar;
+use crate::snippet::{self, Snippet};
+use crate::validate::{self, statement_at, Validation};
+use crate::variables::{self, Replacement};
+use std::collections::HashMap;
 use std::error::{self, Error};
-use std::fs;
-use std::path::PathBuf;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use std::{env, fs};
 
-use crate::custom_table::Table;
-use duckdb::arrow::util::display::{ArrayFormatter, FormatOptions};
-use duckdb::{arrow::array::RecordBatch, Connection};
+use crate::custom_table::{Table, TableState};
+use crate::nested::{cell_node, is_json, is_nested, unnest_query};
+use crate::tree::{self, TreeNode, TreeState};
+use crate::tui::copy_to_clipboard;
+use crossterm::event::{KeyCode, KeyEvent};
+use duckdb::arrow::datatypes::DataType;
+use duckdb::types::Value;
+use duckdb::{arrow::array::RecordBatch, params_from_iter, Connection};
 use ratatui::widgets::ScrollbarState;
 use ratatui_explorer::FileExplorer;
-use tui_textarea::TextArea;
+use tui_textarea::CursorMove;
+
+/// Number of `.read` commands that can be nested, to stop files reading themselves.
+const MAX_READ_DEPTH: usize = 16;
 
 /// Application result type.
 pub type AppResult<T> = std::result::Result<T, Box<dyn error::Error>>;
 
+/// Actions that lose unsaved changes and ask to be confirmed first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Confirm {
+    Quit,
+    CloseBuffer,
+}
+
+/// Long-running work started by a key.
+///
+/// It runs after the next frame is drawn, so the status bar shows it while
+/// the terminal waits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Job {
+    Query,
+    Profile,
+    ProfileFile,
+    Plan,
+}
+
+impl Job {
+    pub fn description(self) -> &'static str {
+        match self {
+            Job::Query => "Running query",
+            Job::Profile => "Profiling results",
+            Job::ProfileFile => "Profiling file",
+            Job::Plan => "Profiling query plan",
+        }
+    }
+}
+
+/// Where SQL that is run came from in the editor text.
+#[derive(Debug, Clone)]
+pub struct Origin {
+    /// Byte offset of the SQL in the editor text.
+    pub offset: usize,
+    /// The SQL with its variables substituted.
+    pub sql: String,
+    /// Variables substituted in the SQL.
+    pub replacements: Vec<Replacement>,
+}
+
+impl Origin {
+    /// Translates a position in `statement`, part of the substituted SQL, to
+    /// a position in the editor text `input`.
+    fn locate(
+        &self,
+        statement: &str,
+        position: (usize, usize),
+        input: &str,
+    ) -> Option<(usize, usize)> {
+        let start = self.sql.find(statement)?;
+        let offset = start + diagnostic::offset_of(statement, position)?;
+        let offset = self.offset + variables::original_offset(&self.replacements, offset);
+        Some(diagnostic::position_of(input, offset))
+    }
+}
+
 /// Application.
 #[derive(Debug)]
 pub struct App {
     pub running: bool,
     pub connection: Connection,
+    /// Database file of the connection, `None` when in memory.
+    pub database: Option<PathBuf>,
+    /// Whether an explicit transaction is open.
+    pub in_transaction: bool,
+    /// Job waiting to run after the next frame, shown in the status bar.
+    pub job: Option<Job>,
     pub input: String,
-    pub table: Table,
-    pub table_state: TableState,
-    pub results: Vec<RecordBatch>,
+    /// Result sets, of which there is always at least one.
+    pub tabs: Vec<ResultTab>,
+    pub active_tab: usize,
+    /// Number of tabs created so far, used to name new tabs.
+    pub tab_count: usize,
+    /// Number of temp tables created so far, used to name them uniquely.
+    pub temp_count: usize,
+    pub show_rename_popup: bool,
+    pub rename_popup: InputPopup,
     pub error: Option<String>,
     pub show_error_popup: bool,
+    /// Structured form of the last error.
+    pub diagnostic: Option<Diagnostic>,
+    pub error_scroll: u16,
+    /// Index of the selected suggestion in the error popup.
+    pub error_selected: usize,
+    /// Whether the editor selection marks the position of the last error.
+    pub error_underline: bool,
+    /// Where the SQL being run came from in the editor, to place its errors.
+    pub origin: Option<Origin>,
+    /// Position of the last error in the editor text, when it was run from there.
+    pub error_position: Option<(usize, usize)>,
+    pub notifications: Notifications,
+    pub show_log: bool,
+    /// Number of lines the message log is scrolled up from its end.
+    pub log_scroll: usize,
     pub vertical_scroll: usize,
     pub horizontal_scroll: usize,
     pub vertical_scroll_state: ScrollbarState,
     pub horizontal_scroll_state: ScrollbarState,
-    pub textarea: TextArea<'static>,
+    /// Query editor buffers, shown as tabs over the editor.
+    pub buffers: Vec<QueryBuffer>,
+    pub active_buffer: usize,
+    /// Number of untitled buffers created so far, used to name new ones.
+    pub buffer_count: usize,
+    /// Keymap of the query editor.
+    pub editor_mode: EditorMode,
+    /// Action waiting for the same key to be pressed again.
+    pub confirm: Option<Confirm>,
+    pub show_search: bool,
+    pub search: SearchBar,
+    /// Problem found in the statement under the cursor while typing.
+    pub validation: Option<Validation>,
+    /// Time of the last edit that has not been validated yet.
+    pub last_edit: Option<Instant>,
+    /// Whether the active buffer should be opened in an external editor.
+    pub external_edit: bool,
+    pub config: Config,
+    pub snippets: Vec<Snippet>,
+    /// Snippet whose placeholders are being filled in.
+    pub snippet: Option<snippet::Session>,
+    pub show_snippet_picker: bool,
+    pub snippet_picker: PickerPopup,
+    /// Queries of the library, loaded when it is opened.
+    pub library: Vec<SavedQuery>,
+    pub show_library: bool,
+    pub library_picker: PickerPopup,
+    pub show_library_save: bool,
+    pub library_form: FormPopup,
+    pub show_params_popup: bool,
+    pub params_form: FormPopup,
+    /// Query waiting for the values of its parameters.
+    pub params_query: String,
+    pub parameters: Vec<Parameter>,
+    /// Last values given for the parameters of each query.
+    pub param_values: HashMap<String, Vec<(String, String)>>,
+    /// Editor query with its variables substituted, or why they can't be.
+    pub preview: Option<Result<String, String>>,
+    /// Whether the key bindings are shown.
+    pub show_help: bool,
+    /// How results are shown and written with `.output`, set with `.mode`.
+    pub result_mode: output::Mode,
+    /// Whether column types are shown under the headers of results.
+    pub show_types: bool,
+    /// File the results of queries are written to, set with `.output`.
+    pub output: Option<PathBuf>,
+    /// Whether run times are reported, set with `.timer`.
+    pub timer: bool,
+    /// Number of rows kept from results, set with `.maxrows`.
+    pub max_rows: Option<usize>,
     pub file_explorer: FileExplorer,
     pub show_save_popup: bool,
     pub save_popup: FileNamePopup,
+    pub show_record_view: bool,
+    pub record: Vec<TreeNode>,
+    pub record_state: TreeState,
+    pub show_cell_inspector: bool,
+    pub inspector: Vec<TreeNode>,
+    pub inspector_state: TreeState,
+    pub profile: Option<Profile>,
+    pub profile_state: ProfileState,
+    pub chart: ChartConfig,
+    /// Data of the chart view, present while it is shown.
+    pub chart_data: Option<ChartData>,
+    pub show_diff_popup: bool,
+    pub diff_popup: InputPopup,
+    /// Column the next comparison matches rows on.
+    pub diff_key: Option<String>,
+    pub diff: Option<Diff>,
+    pub diff_state: TableState,
+    pub plan: Option<Plan>,
+    pub plan_state: TreeState,
 }
 
 impl App {
     pub fn new() -> AppResult<Self> {
-        Ok(Self {
+        let (config, mut warnings) = Config::load();
+        let snippet_dir = Config::dir().unwrap_or_default().join("snippets");
+        let (snippets, snippet_warnings) = snippet::load(&snippet_dir);
+        warnings.extend(snippet_warnings);
+        let mut app = Self {
             running: true,
             connection: Connection::open_in_memory()?,
+            database: None,
+            in_transaction: false,
+            job: None,
             input: String::new(),
-            results: Vec::new(),
+            tabs: vec![ResultTab::empty("Result 1".to_string())],
+            active_tab: 0,
+            tab_count: 1,
+            temp_count: 0,
+            show_rename_popup: false,
+            rename_popup: InputPopup::new("Rename Result", "Name"),
             error: None,
             show_error_popup: false,
+            diagnostic: None,
+            error_scroll: 0,
+            error_selected: 0,
+            error_underline: false,
+            origin: None,
+            error_position: None,
+            notifications: Notifications::default(),
+            show_log: false,
+            log_scroll: 0,
             vertical_scroll: 0,
             horizontal_scroll: 0,
             vertical_scroll_state: ScrollbarState::default(),
             horizontal_scroll_state: ScrollbarState::default(),
-            textarea: TextArea::default(),
+            buffers: vec![QueryBuffer::new("untitled 1".to_string())],
+            active_buffer: 0,
+            buffer_count: 1,
+            editor_mode: EditorMode::default(),
+            confirm: None,
+            show_search: false,
+            search: SearchBar::default(),
+            validation: None,
+            last_edit: None,
+            external_edit: false,
+            config,
+            snippets,
+            snippet: None,
+            show_snippet_picker: false,
+            snippet_picker: PickerPopup::new("Snippets"),
+            library: Vec::new(),
+            show_library: false,
+            library_picker: PickerPopup::new("Library (Enter inserts, Alt+Enter runs)"),
+            show_library_save: false,
+            library_form: FormPopup::new("Save to library", &["Name", "Description", "Tags"]),
+            show_params_popup: false,
+            params_form: FormPopup::new("Parameters", &[]),
+            params_query: String::new(),
+            parameters: Vec::new(),
+            param_values: HashMap::new(),
+            preview: None,
+            show_help: false,
+            result_mode: output::Mode::default(),
+            show_types: false,
+            output: None,
+            timer: false,
+            max_rows: None,
             file_explorer: FileExplorer::new()?,
-            table: Table::default(),
-            table_state: TableState::default(),
             show_save_popup: false,
             save_popup: FileNamePopup::new(),
-        })
+            show_record_view: false,
+            record: Vec::new(),
+            record_state: TreeState::default(),
+            show_cell_inspector: false,
+            inspector: Vec::new(),
+            inspector_state: TreeState::default(),
+            profile: None,
+            profile_state: ProfileState::default(),
+            chart: ChartConfig::default(),
+            chart_data: None,
+            show_diff_popup: false,
+            diff_popup: InputPopup::new("Compare", "Tab number or query"),
+            diff_key: None,
+            diff: None,
+            diff_state: TableState::default(),
+            plan: None,
+            plan_state: TreeState::default(),
+        };
+        for warning in warnings {
+            app.notify(Level::Warning, warning);
+        }
+        Ok(app)
+    }
+
+    /// Starts `job`, or hides the panel it would show when it is already shown.
+    pub fn start_job(&mut self, job: Job) {
+        match job {
+            Job::Profile if self.profile.is_some() => self.profile = None,
+            Job::Plan if self.plan.is_some() => self.plan = None,
+            job => self.job = Some(job),
+        }
+    }
+
+    /// Runs the job started by a key, once the frame showing it was drawn.
+    pub fn run_job(&mut self) {
+        match self.job.take() {
+            Some(Job::Query) => self.run_query(),
+            Some(Job::Profile) => self.toggle_profile(),
+            Some(Job::ProfileFile) => self.profile_file(),
+            Some(Job::Plan) => self.toggle_plan(),
+            None => {}
+        }
+    }
+
+    /// Runs the query in the editor.
+    pub fn run_query(&mut self) {
+        let input = self.input.clone();
+        self.run_script(&input, 0, Some(0));
     }
-    pub fn create_table(&mut self) -> Result<(), Box<dyn Error>> {
-        let mut stmt = self.connection.prepare(&self.input)?;
-        self.results = stmt.query_arrow([])?.collect();
 
-        let options = FormatOptions::default();
+    /// Runs a script of SQL and dot-commands.
+    pub fn run_sql(&mut self, sql: String) {
+        self.run_script(&sql, 0, None);
+    }
+
+    /// Runs the SQL and dot-commands of `script` in order, up to the first
+    /// error or query with parameters.
+    ///
+    /// `depth` is the number of `.read` commands the script was read by, and
+    /// `offset` where it starts in the editor text, if it comes from there.
+    fn run_script(&mut self, script: &str, depth: usize, offset: Option<usize>) {
+        let pieces = dot::split(script);
+        let count = pieces.len();
+        for (i, (start, piece)) in pieces.into_iter().enumerate() {
+            match piece {
+                Piece::Sql(sql) => self.run_statements(sql, offset.map(|offset| offset + start)),
+                Piece::Command(line) => self.run_command(&line, depth),
+            }
+            if self.show_error_popup {
+                return;
+            }
+            if self.show_params_popup {
+                if i + 1 < count {
+                    self.notify(
+                        Level::Warning,
+                        "The rest of the script after a query with parameters is not run",
+                    );
+                }
+                return;
+            }
+        }
+    }
+
+    /// Runs `sql` with its variables substituted, or shows the plan of an
+    /// `EXPLAIN ANALYZE` query.
+    ///
+    /// A query with parameters asks for their values first.
+    ///
+    /// `offset` is where `sql` starts in the editor text, if it comes from there.
+    fn run_statements(&mut self, sql: String, offset: Option<usize>) {
+        let (sql, replacements) =
+            match variables::substitute_mapped(&sql, |name| self.variable(name)) {
+                Ok(substituted) => substituted,
+                Err(e) => {
+                    self.show_error(format!("Error: {e}"), None);
+                    return;
+                }
+            };
+        self.origin = offset.map(|offset| Origin {
+            offset,
+            sql: sql.clone(),
+            replacements,
+        });
+        if analyzed_query(&sql).is_some() {
+            self.explain(&sql);
+            return;
+        }
+        match params::parameters(self, &sql) {
+            Ok(parameters) if !parameters.is_empty() => self.ask_parameters(sql, parameters),
+            _ => self.execute(&sql, &[]),
+        }
+    }
+
+    /// Runs the dot-command on `line`, after substituting its variables.
+    fn run_command(&mut self, line: &str, depth: usize) {
+        self.origin = None;
+        let command = match self
+            .substitute(line)
+            .and_then(|line| DotCommand::parse(&line))
+        {
+            Ok(command) => command,
+            Err(e) => {
+                self.show_error(format!("Error: {e}"), Some(line));
+                return;
+            }
+        };
+        if let Some(sql) = command.query() {
+            self.execute(&sql, &[]);
+            return;
+        }
+        match command {
+            DotCommand::Read(path) if depth >= MAX_READ_DEPTH => {
+                let message = format!("Error: .read {} is nested too deeply", path.display());
+                self.show_error(message, Some(line));
+            }
+            DotCommand::Read(path) => match fs::read_to_string(&path) {
+                Ok(script) => self.run_script(&script, depth + 1, None),
+                Err(e) => {
+                    let message = format!("Error: cannot open \"{}\": {e}", path.display());
+                    self.show_error(message, Some(line));
+                }
+            },
+            DotCommand::Mode(Some(mode)) => self.set_result_mode(mode),
+            DotCommand::Mode(None) => {
+                let message = format!("current output mode: {}", self.result_mode);
+                self.notify(Level::Info, message);
+            }
+            DotCommand::Timer(timer) => {
+                self.timer = timer;
+                let state = if timer { "on" } else { "off" };
+                self.notify(Level::Info, format!("Timer {state}"));
+            }
+            DotCommand::Output(path) => self.set_output(path, line),
+            DotCommand::Open(path) => self.open_database(path, line),
+            DotCommand::MaxRows(Some(max_rows)) => {
+                self.max_rows = (max_rows > 0).then_some(max_rows);
+                self.notify(Level::Info, format!("maxrows: {max_rows}"));
+            }
+            DotCommand::MaxRows(None) => {
+                let message = format!("maxrows: {}", self.max_rows.unwrap_or(0));
+                self.notify(Level::Info, message);
+            }
+            // Answered by their query.
+            DotCommand::Tables(_) | DotCommand::Schema(_) | DotCommand::Databases => {}
+        }
+    }
+
+    /// Writes the results of the following queries to the file at `path`,
+    /// emptied first, or stops writing them.
+    fn set_output(&mut self, path: Option<PathBuf>, line: &str) {
+        let Some(path) = path else {
+            if let Some(path) = self.output.take() {
+                let message = format!("Stopped writing results to {}", path.display());
+                self.notify(Level::Info, message);
+            }
+            return;
+        };
+        match fs::write(&path, "") {
+            Ok(()) => {
+                let message = format!("Writing results to {}", path.display());
+                self.notify(Level::Info, message);
+                self.output = Some(path);
+            }
+            Err(e) => {
+                let message = format!("Error: cannot open \"{}\": {e}", path.display());
+                self.show_error(message, Some(line));
+            }
+        }
+    }
+
+    /// Appends the results of the active tab to the `.output` file, in the output mode.
+    fn write_output(&mut self) {
+        let Some(path) = self.output.clone() else {
+            return;
+        };
+        let text = output::render(&self.tab().table, self.result_mode);
+        let result = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .and_then(|mut file| file.write_all(text.as_bytes()));
+        if let Err(e) = result {
+            self.notify(Level::Error, format!("Error: {}: {e}", path.display()));
+        }
+    }
 
-        let headers: Vec<String> = self.results[0]
-            .schema()
-            .fields
+    /// Replaces the connection with one to the database file at `path`, or to
+    /// a new in-memory database.
+    fn open_database(&mut self, path: Option<PathBuf>, line: &str) {
+        let connection = match &path {
+            Some(path) => Connection::open(path),
+            None => Connection::open_in_memory(),
+        };
+        match connection {
+            Ok(connection) => {
+                self.connection = connection;
+                self.in_transaction = false;
+                // Temp tables are gone with the old connection.
+                for tab in &mut self.tabs {
+                    tab.temp_table = None;
+                }
+                let name = path
+                    .as_ref()
+                    .map_or(":memory:".to_string(), |path| path.display().to_string());
+                self.database = path;
+                self.last_edit = Some(Instant::now());
+                self.notify(Level::Success, format!("Opened {name}"));
+            }
+            Err(e) => self.show_error(format!("Error: {e}"), Some(line)),
+        }
+    }
+
+    /// Value of the `${NAME}` variable `name`, from the command line, the
+    /// config file or the environment.
+    pub fn variable(&self, name: &str) -> Option<String> {
+        self.config
+            .variables
+            .get(name)
+            .cloned()
+            .or_else(|| env::var(name).ok())
+    }
+
+    /// Replaces the `${NAME}` variables in `sql` with their values.
+    pub fn substitute(&self, sql: &str) -> Result<String, String> {
+        variables::substitute(sql, |name| self.variable(name))
+    }
+
+    /// Shows the editor query with its variables substituted, or hides it.
+    pub fn toggle_preview(&mut self) {
+        self.preview = match self.preview {
+            Some(_) => None,
+            None => Some(self.substitute(&self.input)),
+        };
+    }
+
+    /// Shows or hides the key bindings.
+    pub fn toggle_help(&mut self) {
+        self.show_help = !self.show_help;
+    }
+
+    /// Shows the form for the values of `parameters` of `sql`, filled in with
+    /// the values last used for the query.
+    fn ask_parameters(&mut self, sql: String, parameters: Vec<Parameter>) {
+        let query = sql.trim().to_string();
+        let last = self.param_values.get(&query);
+        let labels: Vec<String> = parameters
             .iter()
-            .map(|f| f.name().clone())
+            .map(|parameter| match &parameter.type_hint {
+                Some(type_hint) => format!("{} ({type_hint})", parameter.label),
+                None => parameter.label.clone(),
+            })
             .collect();
+        let labels: Vec<&str> = labels.iter().map(String::as_str).collect();
+        let values = parameters
+            .iter()
+            .map(|parameter| {
+                last.and_then(|values| values.iter().find(|(name, _)| *name == parameter.name))
+                    .map_or_else(String::new, |(_, value)| value.clone())
+            })
+            .collect();
+        self.params_form = FormPopup::new("Parameters (Enter runs)", &labels);
+        self.params_form.open(values);
+        self.params_query = query;
+        self.parameters = parameters;
+        self.show_params_popup = true;
+    }
 
-        let rows: Vec<Vec<String>> = (0..self.results[0].num_rows())
-            .map(|row| {
-                self.results[0]
-                    .columns()
-                    .iter()
-                    .map(|c| {
-                        let formatter = ArrayFormatter::try_new(c.as_ref(), &options).unwrap();
-                        formatter.value(row).to_string()
-                    })
-                    .collect()
+    pub fn close_params_popup(&mut self) {
+        self.show_params_popup = false;
+        self.parameters.clear();
+    }
+
+    /// Runs the query waiting for parameters with the values from the form.
+    pub fn run_with_parameters(&mut self) {
+        let entered: Vec<(String, String)> = self
+            .parameters
+            .iter()
+            .enumerate()
+            .map(|(i, parameter)| {
+                (
+                    parameter.name.clone(),
+                    self.params_form.value(i).to_string(),
+                )
             })
             .collect();
+        let values: Vec<Value> = self
+            .parameters
+            .iter()
+            .zip(&entered)
+            .map(|(parameter, (_, input))| params::value(input, parameter.type_hint.as_deref()))
+            .collect();
+        let query = std::mem::take(&mut self.params_query);
+        self.param_values.insert(query.clone(), entered);
+        self.close_params_popup();
+        self.execute(&query, &values);
+    }
+
+    /// Runs `sql` with `params` bound to its parameters and reports the result.
+    fn execute(&mut self, sql: &str, params: &[Value]) {
+        match self.create_table(sql, params) {
+            Ok(()) => {
+                let tab = self.tab();
+                let elapsed = tab.elapsed;
+                let message = format!(
+                    "{} rows in {} ms",
+                    tab.table.rows.len(),
+                    elapsed.as_millis()
+                );
+                self.notify(Level::Success, message);
+                if self.timer {
+                    let message = format!("Run Time (s): real {:.3}", elapsed.as_secs_f64());
+                    self.notify(Level::Info, message);
+                }
+                self.write_output();
+            }
+            Err(e) => {
+                // A failed statement aborts the transaction it ran in.
+                self.in_transaction = false;
+                self.show_error(format!("Error: {}", e), Some(sql));
+            }
+        }
+    }
+
+    pub fn create_table(&mut self, sql: &str, params: &[Value]) -> Result<(), Box<dyn Error>> {
+        let started = Instant::now();
+        let mut results: Vec<RecordBatch> = {
+            let mut stmt = self.connection.prepare(sql)?;
+            let results = stmt.query_arrow(params_from_iter(params))?.collect();
+            results
+        };
+        let elapsed = started.elapsed();
+        let total: usize = results.iter().map(RecordBatch::num_rows).sum();
+        if let Some(max_rows) = self.max_rows.filter(|max_rows| total > *max_rows) {
+            results = first_rows(results, max_rows);
+            self.notify(
+                Level::Info,
+                format!("Showing the first {max_rows} of {total} rows, set by .maxrows"),
+            );
+        }
+        if let Some(in_transaction) = transaction_state(sql) {
+            self.in_transaction = in_transaction;
+        }
+
+        // Pinned tabs are kept, so the results go to a new tab instead.
+        let name = if self.tab().pinned {
+            self.tab_count += 1;
+            format!("Result {}", self.tab_count)
+        } else {
+            self.tab().name.clone()
+        };
+        let mut tab = ResultTab::new(name, sql.to_string(), elapsed, results)?;
+        // Follow-up queries read the results from a temp table, as the values
+        // bound to the parameters are not part of the SQL.
+        if !params.is_empty() {
+            tab.parameterized = true;
+            let temp_table = self.temp_table_name("tab");
+            match tab.store(&self.connection, &temp_table) {
+                Ok(()) => tab.temp_table = Some(temp_table),
+                Err(e) => self.notify(
+                    Level::Warning,
+                    format!("Results can't be charted, compared or profiled: {e}"),
+                ),
+            }
+        }
+        if self.tab().pinned {
+            self.tabs.push(tab);
+            self.active_tab = self.tabs.len() - 1;
+        } else {
+            self.drop_temp_table(self.active_tab);
+            self.tabs[self.active_tab] = tab;
+        }
 
-        self.table = Table::new(headers, rows);
+        self.show_cell_inspector = false;
+        self.chart_data = None;
+        self.diff = None;
+        self.plan = None;
+        self.load_record();
+        // The query may have changed the schema, so check the editor again.
+        self.last_edit = Some(Instant::now());
 
         Ok(())
     }
 
+    /// Shows results and writes them with `.output` in `mode`.
+    pub fn set_result_mode(&mut self, mode: output::Mode) {
+        self.result_mode = mode;
+        self.notify(Level::Info, format!("Results shown as {mode}"));
+    }
+
+    /// Shows or hides the column types under the headers of results.
+    pub fn toggle_types(&mut self) {
+        self.show_types = !self.show_types;
+    }
+
+    /// Name of the pane that currently receives navigation keys.
+    pub fn focus(&self) -> &'static str {
+        if self.show_error_popup {
+            "Error"
+        } else if self.show_save_popup
+            || self.show_rename_popup
+            || self.show_diff_popup
+            || self.show_snippet_picker
+            || self.show_library
+            || self.show_library_save
+            || self.show_params_popup
+        {
+            "Popup"
+        } else if self.show_cell_inspector {
+            "Inspector"
+        } else if self.plan.is_some() {
+            "Plan"
+        } else if self.diff.is_some() {
+            "Diff"
+        } else if self.chart_data.is_some() {
+            "Chart"
+        } else if self.show_record_view {
+            "Record"
+        } else {
+            "Query"
+        }
+    }
+
+    /// Returns the active result tab.
+    pub fn tab(&self) -> &ResultTab {
+        &self.tabs[self.active_tab]
+    }
+
+    pub fn tab_mut(&mut self) -> &mut ResultTab {
+        &mut self.tabs[self.active_tab]
+    }
+
+    pub fn select_tab(&mut self, index: usize) {
+        if index < self.tabs.len() {
+            self.active_tab = index;
+            self.show_cell_inspector = false;
+            self.chart_data = None;
+            self.load_record();
+        }
+    }
+
+    /// Pins or unpins the active tab, dropping its temp table when unpinned.
+    ///
+    /// Tabs of queries with parameters keep it, since their query can't be run again.
+    pub fn toggle_pin(&mut self) {
+        let tab = self.tab_mut();
+        tab.pinned = !tab.pinned;
+        if !tab.pinned && !tab.parameterized {
+            self.drop_temp_table(self.active_tab);
+        }
+    }
+
+    /// Returns a new name for a temp table, such as `quackmire_tab_1`.
+    fn temp_table_name(&mut self, kind: &str) -> String {
+        self.temp_count += 1;
+        format!("quackmire_{kind}_{}", self.temp_count)
+    }
+
+    /// Pins the active tab and copies its results into a new temp table.
+    pub fn store_temp_table(&mut self) {
+        if self.tab().results.is_empty() {
+            self.notify(Level::Warning, "There are no rows to store");
+            return;
+        }
+        self.drop_temp_table(self.active_tab);
+        let name = self.temp_table_name("tab");
+        match self.tab().store(&self.connection, &name) {
+            Ok(()) => {
+                self.notify(
+                    Level::Success,
+                    format!("Stored results as temp table {name}"),
+                );
+                let tab = self.tab_mut();
+                tab.pinned = true;
+                tab.temp_table = Some(name);
+            }
+            Err(e) => {
+                self.notify(Level::Error, format!("Error: {}", e));
+            }
+        }
+    }
+
+    fn drop_temp_table(&mut self, index: usize) {
+        if let Some(name) = self.tabs[index].temp_table.take() {
+            let sql = format!("DROP TABLE IF EXISTS \"{}\"", name.replace('"', "\"\""));
+            if let Err(e) = self.connection.execute_batch(&sql) {
+                self.notifications
+                    .push(Level::Error, format!("Error: {}", e), Some(&sql));
+            }
+        }
+    }
+
+    /// Closes the active tab, keeping at least one tab open.
+    pub fn close_tab(&mut self) {
+        self.drop_temp_table(self.active_tab);
+        self.tabs.remove(self.active_tab);
+        if self.tabs.is_empty() {
+            self.tab_count += 1;
+            self.tabs
+                .push(ResultTab::empty(format!("Result {}", self.tab_count)));
+        }
+        self.select_tab(self.active_tab.min(self.tabs.len() - 1));
+    }
+
+    pub fn toggle_rename_popup(&mut self) {
+        self.show_rename_popup = !self.show_rename_popup;
+        if self.show_rename_popup {
+            self.rename_popup.input = self.tab().name.clone();
+        } else {
+            self.rename_popup.input.clear();
+        }
+    }
+
+    pub fn rename_tab(&mut self) {
+        let name = self.rename_popup.input.trim().to_string();
+        if !name.is_empty() {
+            self.tab_mut().name = name;
+        }
+        self.toggle_rename_popup();
+    }
+
+    /// Runs `sql` and returns its results formatted as a table.
+    pub fn query_table(&self, sql: &str) -> AppResult<Table> {
+        let mut stmt = self.connection.prepare(sql)?;
+        let results: Vec<RecordBatch> = stmt.query_arrow([])?.collect();
+        Ok(Table::from_batches(&results)?)
+    }
+
     /// Handles the tick event of the terminal.
-    pub fn tick(&self) {}
+    pub fn tick(&mut self) {
+        self.notifications.tick();
+        if self
+            .last_edit
+            .is_some_and(|edited| edited.elapsed() >= validate::IDLE)
+        {
+            self.last_edit = None;
+            self.validate();
+        }
+    }
+
+    /// Checks the statement under the cursor with DuckDB's parser and binder.
+    ///
+    /// Nothing is checked inside a transaction, where a failed statement could
+    /// abort it.
+    pub fn validate(&mut self) {
+        self.validation = None;
+        if self.in_transaction {
+            return;
+        }
+        let buffer = self.buffer();
+        let text = dot::mask(&buffer.text());
+        let Some(mut statement) = statement_at(&text, buffer.textarea.cursor()) else {
+            return;
+        };
+        // Undefined variables are reported when the query runs.
+        let Ok(text) = self.substitute(&statement.text) else {
+            return;
+        };
+        let substituted = text != statement.text;
+        statement.text = text;
+        self.validation = validate::validate(&self.connection, &statement);
+        // Positions in the substituted text don't match the buffer.
+        if let Some(validation) = self.validation.as_mut().filter(|_| substituted) {
+            validation.diagnostic.position = None;
+        }
+    }
+
+    /// Logs a message and shows it as a toast.
+    pub fn notify(&mut self, level: Level, message: impl Into<String>) {
+        self.notifications.push(level, message, None);
+    }
+
+    /// Shows an error in the error popup and logs it with the statement that caused it.
+    ///
+    /// When the statement was run from the editor, the cursor moves to the error position.
+    pub fn show_error(&mut self, message: String, statement: Option<&str>) {
+        self.notifications
+            .push(Level::Error, message.clone(), statement);
+        let diagnostic = Diagnostic::parse(&message, statement.unwrap_or_default());
+        let position = statement
+            .zip(diagnostic.position)
+            .and_then(|(statement, position)| {
+                self.origin
+                    .as_ref()?
+                    .locate(statement, position, &self.input)
+            });
+        if let Some(position) = position {
+            self.locate_error(position, diagnostic.token.as_deref());
+        }
+        self.error_position = position;
+        self.diagnostic = Some(diagnostic);
+        self.error = Some(message);
+        self.error_scroll = 0;
+        self.error_selected = 0;
+        self.show_error_popup = true;
+    }
+
+    /// Moves the editor cursor to the error position, selecting the offending token.
+    fn locate_error(&mut self, (row, col): (usize, usize), token: Option<&str>) {
+        self.buffer_mut().textarea.cancel_selection();
+        self.buffer_mut()
+            .textarea
+            .move_cursor(CursorMove::Jump(row as u16, col as u16));
+        if let Some(token) = token {
+            self.buffer_mut().textarea.start_selection();
+            for _ in 0..token.chars().count() {
+                self.buffer_mut().textarea.move_cursor(CursorMove::Forward);
+            }
+            self.error_underline = true;
+        }
+    }
+
+    pub fn scroll_error(&mut self, amount: isize) {
+        self.error_scroll = self.error_scroll.saturating_add_signed(amount as i16);
+    }
+
+    /// Moves the selection among the suggested fixes.
+    pub fn select_fix(&mut self, amount: isize) {
+        let count = self
+            .diagnostic
+            .as_ref()
+            .map_or(0, |diagnostic| diagnostic.suggestions.len());
+        self.error_selected = self
+            .error_selected
+            .saturating_add_signed(amount)
+            .min(count.saturating_sub(1));
+    }
+
+    /// Replaces the offending token in the editor with the selected suggestion.
+    pub fn apply_fix(&mut self) {
+        let Some(diagnostic) = self.diagnostic.take() else {
+            return;
+        };
+        let Some(suggestion) = diagnostic.suggestions.get(self.error_selected) else {
+            self.diagnostic = Some(diagnostic);
+            return;
+        };
+        let (Some(position), Some(token)) = (self.error_position, diagnostic.token.as_deref())
+        else {
+            self.notify(
+                Level::Warning,
+                "The error has no position in the editor to apply the fix at",
+            );
+            self.diagnostic = Some(diagnostic);
+            return;
+        };
+        self.locate_error(position, Some(token));
+        self.buffer_mut().textarea.insert_str(suggestion);
+        self.buffer_edited();
+        self.error_underline = false;
+        self.show_error_popup = false;
+        self.notify(Level::Info, format!("Replaced with {suggestion}"));
+    }
+
+    /// Copies the full error message to the clipboard.
+    pub fn copy_error(&mut self) {
+        let Some(error) = self.error.clone() else {
+            return;
+        };
+        match copy_to_clipboard(&error) {
+            Ok(()) => self.notify(Level::Info, "Copied error to the clipboard"),
+            Err(e) => self.notify(Level::Error, format!("Error: {}", e)),
+        }
+    }
+
+    pub fn toggle_log(&mut self) {
+        self.show_log = !self.show_log;
+        self.log_scroll = 0;
+    }
+
+    /// Scrolls the message log, where positive amounts go back in time.
+    pub fn scroll_log(&mut self, amount: isize) {
+        let last = self.notifications.log.len().saturating_sub(1);
+        self.log_scroll = self.log_scroll.saturating_add_signed(amount).min(last);
+    }
 
     /// Set running to false to quit the application.
     pub fn quit(&mut self) {
@@ -110,15 +1003,1061 @@ impl App {
             .horizontal_scroll_state
             .position(self.horizontal_scroll);
     }
-    pub fn save_to_file(&self, file_name: &str) -> AppResult<()> {
-        let path = PathBuf::from(file_name);
-        fs::write(path, &self.input)?;
+    pub fn save_to_file(&mut self, path: &Path) -> AppResult<()> {
+        fs::write(path, self.buffer().contents())?;
+        let buffer = self.buffer_mut();
+        buffer.name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| path.display().to_string());
+        buffer.path = Some(path.to_path_buf());
+        buffer.dirty = false;
+        self.notify(Level::Success, format!("Saved {}", path.display()));
         Ok(())
     }
+
+    /// Saves the active buffer to its file, asking for a path if it has none.
+    pub fn save(&mut self) {
+        match self.buffer().path.clone() {
+            Some(path) => {
+                if let Err(e) = self.save_to_file(&path) {
+                    self.notify(Level::Error, format!("Error saving file: {}", e));
+                }
+            }
+            None => self.toggle_save_popup(),
+        }
+    }
+
+    /// Saves to the path in the save popup, asking first before overwriting another file.
+    pub fn confirm_save(&mut self) {
+        let path = self.save_popup.path();
+        let overwrites = path.exists()
+            && self.buffer().path.as_ref() != Some(&path)
+            && self.save_popup.overwrite.as_ref() != Some(&path);
+        if overwrites {
+            self.save_popup.overwrite = Some(path);
+            return;
+        }
+        match self.save_to_file(&path) {
+            Ok(()) => self.toggle_save_popup(),
+            Err(e) => self.notify(Level::Error, format!("Error saving file: {}", e)),
+        }
+    }
+
+    /// Returns the active editor buffer.
+    pub fn buffer(&self) -> &QueryBuffer {
+        &self.buffers[self.active_buffer]
+    }
+
+    pub fn buffer_mut(&mut self) -> &mut QueryBuffer {
+        &mut self.buffers[self.active_buffer]
+    }
+
+    /// Passes a key to the editor keymap and carries out what it asks for.
+    pub fn edit(&mut self, key_event: KeyEvent) {
+        let before = self
+            .snippet
+            .as_ref()
+            .map(|_| snippet::Snapshot::of(&self.buffer().textarea));
+        if !self.edit_action(key_event) {
+            self.editor_input(key_event);
+        }
+        self.follow_snippet(before);
+    }
+
+    /// Passes a key to the editor keymap.
+    fn editor_input(&mut self, key_event: KeyEvent) {
+        let textarea = &mut self.buffers[self.active_buffer].textarea;
+        match self.editor_mode.input(key_event, textarea) {
+            EditorAction::None => {}
+            EditorAction::Edited => self.buffer_edited(),
+            EditorAction::Save => self.save(),
+            EditorAction::Quit(force) => self.request_quit(force),
+            EditorAction::SaveQuit => {
+                self.save();
+                if !self.buffer().dirty {
+                    self.request_quit(false);
+                }
+            }
+            EditorAction::Open => self.open_file(),
+            EditorAction::Find => self.open_search(),
+            EditorAction::Unknown(keys) => {
+                self.notify(Level::Warning, format!("Not an editor command: {keys}"));
+            }
+        }
+    }
+
+    /// Runs the keymap action bound to `key_event`, if any.
+    ///
+    /// Returns whether the key was handled.
+    fn edit_action(&mut self, key_event: KeyEvent) -> bool {
+        let Some(action) = self.config.keymap.action(&key_event) else {
+            return false;
+        };
+        if self.editor_mode.is_pending() || action.inserts() && !self.editor_mode.is_inserting() {
+            return false;
+        }
+        let lines = self.buffer().textarea.lines().to_vec();
+        let textarea = &mut self.buffers[self.active_buffer].textarea;
+        let handled = match action {
+            Action::ToggleComment => editing::toggle_comment(textarea),
+            Action::ToggleBlockComment => editing::toggle_block_comment(textarea),
+            Action::NewlineAndIndent => {
+                editing::newline_and_indent(textarea, self.config.format.indent)
+            }
+            Action::AutoPair => match key_event.code {
+                KeyCode::Char(c) => editing::auto_pair(textarea, c),
+                _ => false,
+            },
+            Action::ExpandSnippet => self.step_snippet(true) || self.expand_snippet(),
+            Action::PreviousPlaceholder => self.step_snippet(false),
+        };
+        if handled && self.buffer().textarea.lines() != lines.as_slice() {
+            self.buffer_edited();
+        }
+        handled
+    }
+
+    /// Moves the placeholders of the current snippet along with an edit, or
+    /// ends the snippet when the edit leaves it.
+    fn follow_snippet(&mut self, before: Option<snippet::Snapshot>) {
+        let (Some(before), Some(session)) = (before, &mut self.snippet) else {
+            return;
+        };
+        let textarea = &self.buffers[self.active_buffer].textarea;
+        if !self.editor_mode.is_inserting() || !session.follow(before, textarea) {
+            self.snippet = None;
+        }
+    }
+
+    /// Moves to the next or previous placeholder of the current snippet.
+    ///
+    /// Returns false if no snippet is being filled in.
+    fn step_snippet(&mut self, forward: bool) -> bool {
+        let Some(session) = &mut self.snippet else {
+            return false;
+        };
+        let textarea = &mut self.buffers[self.active_buffer].textarea;
+        if !session.step(forward, textarea) {
+            self.snippet = None;
+        }
+        true
+    }
+
+    /// Expands the snippet whose trigger is the word before the cursor.
+    ///
+    /// Returns false if there is no such snippet.
+    fn expand_snippet(&mut self) -> bool {
+        let textarea = &mut self.buffers[self.active_buffer].textarea;
+        if textarea.selection_range().is_some() {
+            return false;
+        }
+        let (row, col) = textarea.cursor();
+        let before: Vec<char> = textarea.lines()[row].chars().take(col).collect();
+        let length = before
+            .iter()
+            .rev()
+            .take_while(|c| c.is_alphanumeric() || **c == '_' || **c == '-')
+            .count();
+        let word: String = before[col - length..].iter().collect();
+        let Some(snippet) = self.snippets.iter().find(|s| s.trigger == word).cloned() else {
+            return false;
+        };
+        textarea.move_cursor(CursorMove::Jump(row as u16, (col - length) as u16));
+        textarea.start_selection();
+        textarea.move_cursor(CursorMove::Jump(row as u16, col as u16));
+        self.insert_snippet(&snippet);
+        true
+    }
+
+    /// Inserts `snippet` at the cursor, replacing the selection, and selects
+    /// its first placeholder.
+    pub fn insert_snippet(&mut self, snippet: &Snippet) {
+        let file = self.file_explorer.current().path().display().to_string();
+        let table = self.tab().temp_table.clone().unwrap_or_default();
+        let textarea = &mut self.buffers[self.active_buffer].textarea;
+        let selection = editing::selected_text(textarea).unwrap_or_default();
+        self.snippet = snippet::Session::insert(snippet, textarea, |name| match name {
+            "FILE" => Some(file.clone()),
+            "TABLE" => Some(table.clone()),
+            "SELECTION" => Some(selection.clone()),
+            _ => None,
+        });
+        // Placeholders are typed over.
+        if let EditorMode::Vim(vim) = &mut self.editor_mode {
+            if self.snippet.is_some() {
+                vim.mode = VimMode::Insert;
+            }
+        }
+        self.buffer_edited();
+    }
+
+    /// Shows or hides the list of snippets.
+    pub fn toggle_snippet_picker(&mut self) {
+        self.show_snippet_picker = !self.show_snippet_picker;
+        if self.show_snippet_picker {
+            let items = self
+                .snippets
+                .iter()
+                .map(|snippet| PickerItem {
+                    name: snippet.trigger.clone(),
+                    detail: snippet.description.clone(),
+                })
+                .collect();
+            self.snippet_picker.open(items);
+        }
+    }
+
+    /// Shows the query library, or hides it.
+    pub fn toggle_library(&mut self) {
+        self.show_library = !self.show_library;
+        if !self.show_library {
+            return;
+        }
+        let Some(dir) = self.config.library_dir() else {
+            self.show_library = false;
+            self.notify(Level::Error, "Error: no library directory".to_string());
+            return;
+        };
+        let (queries, warnings) = library::load(&dir);
+        for warning in warnings {
+            self.notify(Level::Warning, warning);
+        }
+        let items = queries
+            .iter()
+            .map(|query| {
+                let tags: Vec<String> = query.tags.iter().map(|tag| format!("#{tag}")).collect();
+                PickerItem {
+                    name: query.name.clone(),
+                    detail: format!("{} {}", query.description, tags.join(" "))
+                        .trim()
+                        .to_string(),
+                }
+            })
+            .collect();
+        self.library = queries;
+        self.library_picker.open(items);
+    }
+
+    /// Closes the library and returns the query that was selected in it.
+    fn take_saved_query(&mut self) -> Option<SavedQuery> {
+        let query = self
+            .library_picker
+            .selected_item()
+            .and_then(|i| self.library.get(i))
+            .cloned();
+        self.toggle_library();
+        query
+    }
+
+    /// Inserts the selected library query at the cursor.
+    pub fn insert_saved_query(&mut self) {
+        let Some(query) = self.take_saved_query() else {
+            return;
+        };
+        self.snippet = None;
+        self.buffer_mut().textarea.insert_str(&query.sql);
+        self.buffer_edited();
+    }
+
+    /// Runs the selected library query, leaving the editor as it is.
+    pub fn run_saved_query(&mut self) {
+        let Some(query) = self.take_saved_query() else {
+            return;
+        };
+        self.run_sql(query.sql);
+    }
+
+    /// Asks for the name, description and tags to save the buffer to the
+    /// library under, or hides the popup.
+    ///
+    /// They are filled in from the front matter of a buffer opened from the library.
+    pub fn toggle_library_save(&mut self) {
+        self.show_library_save = !self.show_library_save;
+        if self.show_library_save {
+            let text = self.buffer().text();
+            let query = SavedQuery::parse(&text, "");
+            self.library_form
+                .open(vec![query.name, query.description, query.tags.join(", ")]);
+        }
+    }
+
+    /// Saves the buffer to the library under the name from the popup.
+    pub fn save_to_library(&mut self) {
+        let name = self.library_form.value(0).trim().to_string();
+        if name.is_empty() {
+            self.notify(Level::Warning, "A library query needs a name".to_string());
+            return;
+        }
+        let Some(dir) = self.config.library_dir() else {
+            self.notify(Level::Error, "Error: no library directory".to_string());
+            return;
+        };
+        let text = self.buffer().text();
+        let query = SavedQuery {
+            name,
+            description: self.library_form.value(1).trim().to_string(),
+            tags: library::parse_tags(self.library_form.value(2)),
+            sql: SavedQuery::parse(&text, "").sql,
+        };
+        self.toggle_library_save();
+        match library::save(&dir, &query) {
+            Ok(path) => self.notify(
+                Level::Success,
+                format!("Saved {} to {}", query.name, path.display()),
+            ),
+            Err(e) => self.notify(Level::Error, format!("Error: {e}")),
+        }
+    }
+
+    /// Inserts the snippet selected in the picker.
+    pub fn pick_snippet(&mut self) {
+        let snippet = self
+            .snippet_picker
+            .selected_item()
+            .and_then(|i| self.snippets.get(i))
+            .cloned();
+        self.toggle_snippet_picker();
+        if let Some(snippet) = snippet {
+            self.insert_snippet(&snippet);
+        }
+    }
+
+    /// Inserts pasted text into the active buffer.
+    pub fn paste(&mut self, text: &str) {
+        let before = self
+            .snippet
+            .as_ref()
+            .map(|_| snippet::Snapshot::of(&self.buffer().textarea));
+        let textarea = &mut self.buffers[self.active_buffer].textarea;
+        if self.editor_mode.paste(text, textarea) == EditorAction::Edited {
+            self.buffer_edited();
+        }
+        self.follow_snippet(before);
+    }
+
+    /// Opens the find bar, limited to the selection if there is one.
+    pub fn open_search(&mut self) {
+        self.show_search = true;
+        self.snippet = None;
+        let textarea = &mut self.buffers[self.active_buffer].textarea;
+        self.search.open(textarea);
+    }
+
+    pub fn close_search(&mut self) {
+        self.show_search = false;
+        let textarea = &mut self.buffers[self.active_buffer].textarea;
+        self.search.close(textarea);
+    }
+
+    /// Searches again after the pattern or its options changed.
+    pub fn update_search(&mut self) {
+        let textarea = &mut self.buffers[self.active_buffer].textarea;
+        self.search.update(textarea);
+    }
+
+    /// Selects the next or previous match.
+    pub fn step_search(&mut self, forward: bool) {
+        let textarea = &mut self.buffers[self.active_buffer].textarea;
+        self.search.step(forward, textarea);
+    }
+
+    /// Replaces the current match, or all matches.
+    pub fn replace_matches(&mut self, all: bool) {
+        let textarea = &mut self.buffers[self.active_buffer].textarea;
+        if all {
+            let count = self.search.replace_all(textarea);
+            if count > 0 {
+                self.buffer_edited();
+                self.notify(Level::Info, format!("Replaced {count} matches"));
+            }
+        } else if self.search.replace_current(textarea) {
+            self.buffer_edited();
+        }
+    }
+
+    /// Switches the editor to the next keymap.
+    pub fn cycle_editor_mode(&mut self) {
+        self.editor_mode = self.editor_mode.next();
+        self.buffer_mut().textarea.cancel_selection();
+        let name = self.editor_mode.name();
+        self.notify(Level::Info, format!("Editor keymap: {name}"));
+    }
+
+    /// Marks the active buffer as changed and syncs `input` with it.
+    pub fn buffer_edited(&mut self) {
+        self.buffer_mut().dirty = true;
+        self.input = self.buffer().text();
+        self.validation = None;
+        self.last_edit = Some(Instant::now());
+    }
+
+    pub fn select_buffer(&mut self, index: usize) {
+        if self.show_search {
+            self.close_search();
+        }
+        self.snippet = None;
+        if index < self.buffers.len() {
+            self.active_buffer = index;
+            self.input = self.buffer().text();
+            self.error_underline = false;
+            self.validation = None;
+            self.last_edit = Some(Instant::now());
+        }
+    }
+
+    /// Switches to the next or previous buffer, wrapping around.
+    pub fn cycle_buffer(&mut self, amount: isize) {
+        let count = self.buffers.len() as isize;
+        let index = (self.active_buffer as isize + amount).rem_euclid(count);
+        self.select_buffer(index as usize);
+    }
+
+    pub fn new_buffer(&mut self) {
+        self.buffer_count += 1;
+        self.buffers
+            .push(QueryBuffer::new(format!("untitled {}", self.buffer_count)));
+        self.select_buffer(self.buffers.len() - 1);
+    }
+
+    /// Closes the active buffer, keeping at least one buffer open.
+    ///
+    /// Closing a buffer with unsaved changes has to be confirmed by closing it again.
+    pub fn close_buffer(&mut self, confirmed: bool) {
+        if self.buffer().dirty && !confirmed {
+            let name = self.buffer().name.clone();
+            self.notify(
+                Level::Warning,
+                format!("{name} has unsaved changes, press again to close it"),
+            );
+            self.confirm = Some(Confirm::CloseBuffer);
+            return;
+        }
+        self.buffers.remove(self.active_buffer);
+        if self.buffers.is_empty() {
+            self.new_buffer();
+        } else {
+            self.select_buffer(self.active_buffer.min(self.buffers.len() - 1));
+        }
+    }
+
+    /// Opens the file selected in the explorer in a buffer.
+    ///
+    /// Files that are already open are switched to, and a blank active buffer is reused.
+    pub fn open_file(&mut self) {
+        let path = self.file_explorer.current().path().clone();
+        if !path.is_file() {
+            return;
+        }
+        if let Some(index) = self
+            .buffers
+            .iter()
+            .position(|buffer| buffer.path.as_ref() == Some(&path))
+        {
+            self.select_buffer(index);
+            return;
+        }
+        match QueryBuffer::open(&path) {
+            Ok(buffer) => {
+                if self.buffer().is_blank() {
+                    self.buffers[self.active_buffer] = buffer;
+                    self.select_buffer(self.active_buffer);
+                } else {
+                    self.buffers.push(buffer);
+                    self.select_buffer(self.buffers.len() - 1);
+                }
+            }
+            Err(e) => self.notify(Level::Error, format!("Error: {}", e)),
+        }
+    }
+
+    /// Edits the active buffer in `$VISUAL` or `$EDITOR` and loads the result.
+    ///
+    /// The terminal has to be suspended while the editor runs.
+    pub fn edit_externally(&mut self) {
+        let editor = env::var("VISUAL")
+            .or_else(|_| env::var("EDITOR"))
+            .unwrap_or_else(|_| "vi".to_string());
+        let mut words = editor.split_whitespace();
+        let Some(program) = words.next() else {
+            self.notify(
+                Level::Error,
+                "Error: $VISUAL and $EDITOR are empty".to_string(),
+            );
+            return;
+        };
+        let text = self.buffer().text();
+        // The file is created in a new directory only the user can access, so
+        // other users can't plant or swap it.
+        let result = create_private_dir().and_then(|dir| {
+            let path = dir.join("query.sql");
+            let result = create_private_file(&path)
+                .and_then(|mut file| file.write_all(text.as_bytes()))
+                .and_then(|_| Command::new(program).args(words).arg(&path).status())
+                .and_then(|status| {
+                    if status.success() {
+                        fs::read_to_string(&path).map(Some)
+                    } else {
+                        Ok(None)
+                    }
+                });
+            let _ = fs::remove_dir_all(&dir);
+            result
+        });
+        match result {
+            Ok(Some(edited)) => {
+                let edited = edited.strip_suffix('\n').unwrap_or(&edited);
+                if edited != text {
+                    self.snippet = None;
+                    self.buffer_mut().set_text(edited);
+                    self.buffer_edited();
+                }
+            }
+            Ok(None) => {
+                self.notify(
+                    Level::Warning,
+                    format!("{program} failed, buffer unchanged"),
+                );
+            }
+            Err(e) => self.notify(Level::Error, format!("Error: {program}: {e}")),
+        }
+    }
+
+    /// Formats the selection, or the whole buffer if nothing is selected.
+    pub fn format_query(&mut self) {
+        let options = self.config.format.clone();
+        let buffer = self.buffer_mut();
+        let (text, selected) = match buffer.selected_text() {
+            Some(text) => (text, true),
+            None => (buffer.text(), false),
+        };
+        let formatted = format_sql(&text, &options);
+        if formatted == text {
+            return;
+        }
+        if !selected {
+            buffer.textarea.select_all();
+        }
+        buffer.textarea.insert_str(&formatted);
+        self.snippet = None;
+        self.buffer_edited();
+    }
+
+    /// Quits, unless there are unsaved buffers and the quit is not confirmed.
+    pub fn request_quit(&mut self, confirmed: bool) {
+        let unsaved = self.buffers.iter().filter(|buffer| buffer.dirty).count();
+        if unsaved > 0 && !confirmed {
+            self.notify(
+                Level::Warning,
+                format!("{unsaved} unsaved buffer(s), press Esc again to quit"),
+            );
+            self.confirm = Some(Confirm::Quit);
+        } else {
+            self.quit();
+        }
+    }
+    pub fn toggle_record_view(&mut self) {
+        self.show_record_view = !self.show_record_view;
+        self.tab_mut().table_state.scroll_to_selected();
+        self.load_record();
+    }
+
+    /// Moves the selected result row, keeping it in view.
+    pub fn select_row(&mut self, amount: isize) {
+        if let Some(diff) = &self.diff {
+            let last = diff.changes.len().saturating_sub(1);
+            self.diff_state.selected = self
+                .diff_state
+                .selected
+                .saturating_add_signed(amount)
+                .min(last);
+            self.diff_state.scroll_to_selected();
+            return;
+        }
+        let grid = self.result_mode.is_grid() || self.show_record_view;
+        let tab = self.tab_mut();
+        let last = tab.table.rows.len().saturating_sub(1);
+        tab.table_state.selected = tab
+            .table_state
+            .selected
+            .saturating_add_signed(amount)
+            .min(last);
+        // Text views scroll to the lines of the selected row themselves.
+        if grid {
+            tab.table_state.scroll_to_selected();
+        }
+        self.load_record();
+        if self.show_cell_inspector {
+            self.load_inspector();
+        }
+    }
+
+    /// Moves the selected result column, keeping it in view.
+    pub fn select_column(&mut self, amount: isize) {
+        if let Some(diff) = &self.diff {
+            let last = diff.headers.len().saturating_sub(1);
+            self.diff_state.selected_col = self
+                .diff_state
+                .selected_col
+                .saturating_add_signed(amount)
+                .min(last);
+            self.diff_state.scroll_to_column(&diff.widths);
+            return;
+        }
+        let (grid, show_types) = (self.result_mode.is_grid(), self.show_types);
+        let tab = self.tab_mut();
+        let last = tab.table.headers.len().saturating_sub(1);
+        tab.table_state.selected_col = tab
+            .table_state
+            .selected_col
+            .saturating_add_signed(amount)
+            .min(last);
+        if grid {
+            let widths = tab.table.column_widths(show_types);
+            tab.table_state.scroll_to_column(&widths);
+        }
+        self.profile_state.selected = self.tab().table_state.selected_col;
+        if self.show_cell_inspector {
+            self.load_inspector();
+        }
+    }
+
+    fn load_record(&mut self) {
+        let tab = self.tab();
+        let row = tab.table_state.selected;
+        let record = tab
+            .table
+            .headers
+            .iter()
+            .enumerate()
+            .filter_map(|(col, header)| {
+                tab.cell(row, col)
+                    .map(|(field, array, i)| cell_node(header.clone(), field, array.as_ref(), i))
+            })
+            .collect();
+        self.record = record;
+    }
+
+    fn load_inspector(&mut self) {
+        let tab = self.tab();
+        let (row, col) = (tab.table_state.selected, tab.table_state.selected_col);
+        let header = tab.table.headers.get(col).cloned().unwrap_or_default();
+        let inspector = tab
+            .cell(row, col)
+            .map(|(field, array, i)| {
+                vec![cell_node(header, field, array.as_ref(), i).expanded(true)]
+            })
+            .unwrap_or_default();
+        self.inspector = inspector;
+        self.inspector_state = TreeState::default();
+    }
+
+    pub fn toggle_cell_inspector(&mut self) {
+        self.show_cell_inspector = !self.show_cell_inspector && !self.tab().table.is_empty();
+        if self.show_cell_inspector {
+            self.load_inspector();
+        }
+    }
+
+    /// Moves the selection of the tree that has focus.
+    pub fn select_node(&mut self, amount: isize) {
+        if self.show_cell_inspector {
+            self.inspector_state.select(amount);
+        } else if self.plan.is_some() {
+            self.plan_state.select(amount);
+        } else {
+            self.record_state.select(amount);
+        }
+    }
+
+    /// Expands or collapses the selected node of the tree that has focus.
+    pub fn toggle_node(&mut self) {
+        if self.show_cell_inspector {
+            tree::toggle(&mut self.inspector, self.inspector_state.selected);
+        } else if let Some(plan) = &mut self.plan {
+            tree::toggle(&mut plan.nodes, self.plan_state.selected);
+        } else {
+            tree::toggle(&mut self.record, self.record_state.selected);
+        }
+    }
+
+    /// Shows the profile of the current results, or hides the profile panel.
+    ///
+    /// The fetched rows are profiled, copied into a temp table unless the tab
+    /// is already stored in one, so the query is not run again.
+    pub fn toggle_profile(&mut self) {
+        if self.profile.is_some() {
+            self.profile = None;
+        } else if self.tab().temp_table.is_some() {
+            let source = self.tab().source();
+            self.load_profile("Profile: results".to_string(), &source);
+        } else if !self.tab().results.is_empty() {
+            // The results are copied into a temp table only while they are profiled.
+            let name = self.temp_table_name("profile");
+            if let Err(e) = self.tab().store(&self.connection, &name) {
+                self.notify(Level::Error, format!("Error: {}", e));
+                return;
+            }
+            self.load_profile("Profile: results".to_string(), &format!("temp.{name}"));
+            let sql = format!("DROP TABLE IF EXISTS temp.{name}");
+            if let Err(e) = self.connection.execute_batch(&sql) {
+                self.notify(Level::Error, format!("Error: {}", e));
+            }
+        }
+    }
+
+    /// Shows the profile of the file selected in the explorer.
+    pub fn profile_file(&mut self) {
+        let path = self.file_explorer.current().path().clone();
+        if path.is_file() {
+            let source = format!("'{}'", path.display().to_string().replace('\'', "''"));
+            let title = format!("Profile: {}", self.file_explorer.current().name());
+            self.load_profile(title, &source);
+        }
+    }
+
+    fn load_profile(&mut self, title: String, source: &str) {
+        match Profile::load(self, title, source) {
+            Ok(profile) => {
+                self.profile = Some(profile);
+                self.profile_state = ProfileState {
+                    offset: 0,
+                    selected: self.tab().table_state.selected_col,
+                };
+            }
+            Err(e) => {
+                self.notify(Level::Error, format!("Error: {}", e));
+            }
+        }
+    }
+
+    /// Shows a chart of the current results, or hides it.
+    pub fn toggle_chart(&mut self) {
+        if self.chart_data.is_some() {
+            self.chart_data = None;
+        } else if !self.tab().table.is_empty() {
+            let tab = self.tab();
+            self.chart = ChartConfig::for_table(&tab.table, tab.table_state.selected_col);
+            self.load_chart();
+        }
+    }
+
+    pub fn next_chart_kind(&mut self) {
+        self.chart.kind = self.chart.kind.next();
+        self.load_chart();
+    }
+
+    /// Uses the selected column for the x axis.
+    pub fn set_chart_x(&mut self) {
+        self.chart.x = self.tab().table_state.selected_col;
+        self.load_chart();
+    }
+
+    /// Adds or removes the selected column from the plotted series.
+    pub fn toggle_chart_series(&mut self) {
+        let column = self.tab().table_state.selected_col;
+        self.chart.toggle_series(column);
+        self.load_chart();
+    }
+
+    fn load_chart(&mut self) {
+        match ChartData::load(self, &self.chart) {
+            Ok(data) => self.chart_data = Some(data),
+            Err(e) => {
+                self.notify(Level::Error, format!("Error: {}", e));
+            }
+        }
+    }
+
+    /// Shows the profiled plan of the statement under the cursor, or hides the plan view.
+    pub fn toggle_plan(&mut self) {
+        if self.plan.is_some() {
+            self.plan = None;
+            return;
+        }
+        // Profiling runs the statement, so the rest of a script must not be part of it.
+        let text = dot::mask(&self.input);
+        let Some(statement) = statement_at(&text, self.buffer().textarea.cursor()) else {
+            return;
+        };
+        let offset = diagnostic::offset_of(&text, statement.start).unwrap_or_default();
+        match variables::substitute_mapped(&statement.text, |name| self.variable(name)) {
+            Ok((sql, replacements)) => {
+                self.origin = Some(Origin {
+                    offset,
+                    sql: sql.clone(),
+                    replacements,
+                });
+                self.explain(&sql);
+            }
+            Err(e) => self.show_error(format!("Error: {e}"), None),
+        }
+    }
+
+    /// Runs the query with profiling and shows its operator tree.
+    ///
+    /// An `EXPLAIN ANALYZE` prefix is dropped, as the profile replaces its output.
+    pub fn explain(&mut self, sql: &str) {
+        let sql = analyzed_query(sql).unwrap_or(sql);
+        let sql = sql.trim().trim_end_matches(';');
+        if sql.is_empty() {
+            return;
+        }
+        match Plan::load(&self.connection, sql) {
+            Ok(plan) => {
+                self.plan = Some(plan);
+                self.plan_state = TreeState::default();
+            }
+            Err(e) => {
+                let sql = sql.to_string();
+                self.show_error(format!("Error: {}", e), Some(&sql));
+            }
+        }
+    }
+
+    /// Asks what to compare the active tab with, or hides the diff view.
+    ///
+    /// With `by_key`, rows are matched on the selected column instead of the whole row.
+    pub fn toggle_diff_popup(&mut self, by_key: bool) {
+        if self.diff.is_some() {
+            self.diff = None;
+        } else if self.show_diff_popup {
+            self.show_diff_popup = false;
+            self.diff_popup.input.clear();
+        } else if !self.tab().query.trim().is_empty() {
+            let tab = &self.tabs[self.active_tab];
+            self.diff_key = if by_key {
+                tab.table.headers.get(tab.table_state.selected_col).cloned()
+            } else {
+                None
+            };
+            self.diff_popup.title = match &self.diff_key {
+                Some(key) => format!("Compare {} on {}", tab.name, key),
+                None => format!("Compare {}", tab.name),
+            };
+            self.show_diff_popup = true;
+        }
+    }
+
+    /// Compares the active tab with the tab number or query entered in the popup.
+    pub fn compare(&mut self) {
+        let input = self.diff_popup.input.trim().to_string();
+        self.show_diff_popup = false;
+        self.diff_popup.input.clear();
+        if input.is_empty() {
+            return;
+        }
+
+        let right = match input.parse::<usize>() {
+            Ok(n) if (1..=self.tabs.len()).contains(&n) => {
+                let tab = &self.tabs[n - 1];
+                (tab.name.clone(), tab.source())
+            }
+            _ => (
+                "query".to_string(),
+                format!("({})", input.trim_end_matches(';')),
+            ),
+        };
+        let left = (self.tab().name.clone(), self.tab().source());
+        match Diff::load(
+            self,
+            (left.0, &left.1),
+            (right.0, &right.1),
+            self.diff_key.clone(),
+        ) {
+            Ok(diff) => {
+                self.notify(
+                    Level::Info,
+                    format!("{} differing rows", diff.changes.len()),
+                );
+                self.diff = Some(diff);
+                self.diff_state = TableState::default();
+            }
+            Err(e) => {
+                self.notify(Level::Error, format!("Error: {}", e));
+            }
+        }
+    }
+
+    /// Replaces the query with one that unnests the selected nested column.
+    pub fn unnest_selected(&mut self) {
+        let col = if self.show_record_view {
+            tree::root_of(&self.record, self.record_state.selected)
+        } else {
+            Some(self.tab().table_state.selected_col)
+        };
+        let Some(field) = col.and_then(|col| {
+            self.tab()
+                .results
+                .first()
+                .and_then(|batch| batch.schema().fields.get(col).cloned())
+        }) else {
+            return;
+        };
+
+        if !is_nested(field.data_type()) && !is_json(&field) {
+            self.notify(
+                Level::Warning,
+                format!("Column {} is not a nested type", field.name()),
+            );
+            return;
+        }
+
+        let query = unnest_query(&self.tab().source(), field.name(), field.data_type());
+        self.buffer_mut().textarea.select_all();
+        self.buffer_mut().textarea.cut();
+        self.buffer_mut().textarea.insert_str(&query);
+        self.buffer_mut().textarea.move_cursor(CursorMove::Top);
+        self.buffer_edited();
+    }
+
+    /// Opens the Save As popup in the explorer's directory, or closes it.
     pub fn toggle_save_popup(&mut self) {
         self.show_save_popup = !self.show_save_popup;
-        if !self.show_save_popup {
+        if self.show_save_popup {
+            let name = self
+                .buffer()
+                .path
+                .as_ref()
+                .and_then(|path| path.file_name())
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            self.save_popup.open(self.file_explorer.cwd().clone(), name);
+        } else {
             self.save_popup.input.clear();
         }
     }
 }
+
+/// Returns whether `sql` leaves a transaction open, if it begins or ends one.
+///
+/// Only `;` outside quotes and comments separate statements.
+fn transaction_state(sql: &str) -> Option<bool> {
+    validate::statements(sql)
+        .iter()
+        .filter_map(
+            |statement| match validate::keyword(&statement.text).as_str() {
+                "BEGIN" | "START" => Some(true),
+                "COMMIT" | "END" | "ROLLBACK" | "ABORT" => Some(false),
+                _ => None,
+            },
+        )
+        .next_back()
+}
+
+/// Keeps the first `count` rows of `batches`.
+fn first_rows(batches: Vec<RecordBatch>, count: usize) -> Vec<RecordBatch> {
+    let mut left = count;
+    batches
+        .into_iter()
+        .map_while(|batch| {
+            if left == 0 {
+                return None;
+            }
+            let rows = batch.num_rows().min(left);
+            left -= rows;
+            Some(batch.slice(0, rows))
+        })
+        .collect()
+}
+
+/// Returns the DuckDB name of an Arrow data type.
+pub fn type_name(data_type: &DataType) -> String {
+    match data_type {
+        DataType::Null => "NULL".to_string(),
+        DataType::Boolean => "BOOLEAN".to_string(),
+        DataType::Int8 => "TINYINT".to_string(),
+        DataType::Int16 => "SMALLINT".to_string(),
+        DataType::Int32 => "INTEGER".to_string(),
+        DataType::Int64 => "BIGINT".to_string(),
+        DataType::UInt8 => "UTINYINT".to_string(),
+        DataType::UInt16 => "USMALLINT".to_string(),
+        DataType::UInt32 => "UINTEGER".to_string(),
+        DataType::UInt64 => "UBIGINT".to_string(),
+        DataType::Float16 | DataType::Float32 => "FLOAT".to_string(),
+        DataType::Float64 => "DOUBLE".to_string(),
+        DataType::Decimal128(p, s) | DataType::Decimal256(p, s) => format!("DECIMAL({p},{s})"),
+        DataType::Utf8 | DataType::LargeUtf8 | DataType::Utf8View => "VARCHAR".to_string(),
+        DataType::Binary
+        | DataType::LargeBinary
+        | DataType::BinaryView
+        | DataType::FixedSizeBinary(_) => "BLOB".to_string(),
+        DataType::Date32 | DataType::Date64 => "DATE".to_string(),
+        DataType::Time32(_) | DataType::Time64(_) => "TIME".to_string(),
+        DataType::Timestamp(_, Some(_)) => "TIMESTAMP WITH TIME ZONE".to_string(),
+        DataType::Timestamp(_, None) => "TIMESTAMP".to_string(),
+        DataType::Duration(_) | DataType::Interval(_) => "INTERVAL".to_string(),
+        DataType::List(f) | DataType::LargeList(f) | DataType::ListView(f) => {
+            format!("{}[]", type_name(f.data_type()))
+        }
+        DataType::FixedSizeList(f, n) => format!("{}[{n}]", type_name(f.data_type())),
+        DataType::Struct(fields) => format!(
+            "STRUCT({})",
+            fields
+                .iter()
+                .map(|f| format!("{} {}", field_name(f.name()), type_name(f.data_type())))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        DataType::Map(entries, _) => match entries.data_type() {
+            DataType::Struct(kv) if kv.len() == 2 => format!(
+                "MAP({}, {})",
+                type_name(kv[0].data_type()),
+                type_name(kv[1].data_type())
+            ),
+            other => type_name(other),
+        },
+        DataType::Union(fields, _) => format!(
+            "UNION({})",
+            fields
+                .iter()
+                .map(|(_, f)| format!("{} {}", field_name(f.name()), type_name(f.data_type())))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        DataType::Dictionary(_, value) => type_name(value),
+        other => other.to_string(),
+    }
+}
+
+/// Quotes a STRUCT or UNION member name unless it is a plain identifier.
+fn field_name(name: &str) -> String {
+    let plain = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if plain {
+        name.to_string()
+    } else {
+        format!("\"{}\"", name.replace('"', "\"\""))
+    }
+}
+
+/// Creates a new directory in the system temp directory that only the user can access.
+fn create_private_dir() -> io::Result<PathBuf> {
+    let mut builder = fs::DirBuilder::new();
+    #[cfg(unix)]
+    std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.subsec_nanos());
+    for attempt in 0..100 {
+        let name = format!("quackmire-{}-{nanos}-{attempt}", std::process::id());
+        let dir = env::temp_dir().join(name);
+        match builder.create(&dir) {
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            result => return result.map(|_| dir),
+        }
+    }
+    Err(io::Error::new(
+        io::ErrorKind::AlreadyExists,
+        "no unused temporary directory name",
+    ))
+}
+
+/// Creates the file at `path`, failing if anything exists there, readable only by the user.
+fn create_private_file(path: &Path) -> io::Result<fs::File> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options.open(path)
+}
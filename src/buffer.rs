@@ -0,0 +1,170 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use tui_textarea::TextArea;
+
+use crate::editing;
+
+/// A query editor buffer, optionally backed by a file.
+#[derive(Debug, Clone)]
+pub struct QueryBuffer {
+    pub name: String,
+    pub textarea: TextArea<'static>,
+    pub path: Option<PathBuf>,
+    /// Whether the buffer has changes that are not saved to its file.
+    pub dirty: bool,
+    /// Whether the file ended with a newline, kept when saving it back.
+    pub trailing_newline: bool,
+    /// First row and column of the text shown in the editor.
+    pub scroll: (u16, u16),
+}
+
+impl QueryBuffer {
+    /// Creates an empty buffer without a file.
+    pub fn new(name: String) -> Self {
+        Self {
+            name,
+            textarea: TextArea::default(),
+            path: None,
+            dirty: false,
+            trailing_newline: false,
+            scroll: (0, 0),
+        }
+    }
+
+    /// Creates a buffer with the contents of the file at `path`.
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let content = fs::read_to_string(path)?;
+        let mut textarea = TextArea::from(content.lines());
+        textarea.move_cursor(tui_textarea::CursorMove::Top);
+        Ok(Self {
+            name: path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_else(|| path.display().to_string()),
+            textarea,
+            path: Some(path.to_path_buf()),
+            dirty: false,
+            trailing_newline: content.ends_with('\n'),
+            scroll: (0, 0),
+        })
+    }
+
+    /// Returns the text of the buffer.
+    pub fn text(&self) -> String {
+        self.textarea.lines().join("\n")
+    }
+
+    /// Returns the text to write to the buffer's file.
+    pub fn contents(&self) -> String {
+        let mut text = self.text();
+        if self.trailing_newline {
+            text.push('\n');
+        }
+        text
+    }
+
+    /// Returns the selected text, if there is a selection.
+    pub fn selected_text(&self) -> Option<String> {
+        editing::selected_text(&self.textarea)
+    }
+
+    /// Replaces the text of the buffer through the editor, so the change can be
+    /// undone, keeping the cursor where it was if possible.
+    pub fn set_text(&mut self, text: &str) {
+        let (row, col) = self.textarea.cursor();
+        self.textarea.select_all();
+        self.textarea.insert_str(text);
+        self.textarea
+            .move_cursor(tui_textarea::CursorMove::Jump(row as u16, col as u16));
+    }
+
+    /// Scrolls the editor so the cursor stays in an area of `width` by `height`
+    /// cells, the same way the text area does when it is rendered.
+    pub fn scroll_to_cursor(&mut self, width: u16, height: u16) {
+        let (row, col) = self.textarea.cursor();
+        let gutter = self.textarea.lines().len().to_string().len() as u16 + 2;
+        let col = col as u16;
+        // The line numbers slide back in twice as fast when scrolling left.
+        let col = if col <= gutter {
+            col.saturating_mul(2)
+        } else {
+            col.saturating_add(gutter)
+        };
+        self.scroll = (
+            next_scroll_top(self.scroll.0, row as u16, height),
+            next_scroll_top(self.scroll.1, col, width),
+        );
+    }
+
+    /// Returns true for a buffer without a file, content or changes.
+    pub fn is_blank(&self) -> bool {
+        self.path.is_none() && !self.dirty && self.text().is_empty()
+    }
+
+    /// Title shown in the buffer tab bar.
+    pub fn title(&self) -> String {
+        if self.dirty {
+            format!("{} ●", self.name)
+        } else {
+            self.name.clone()
+        }
+    }
+}
+
+/// First row or column to show so that `cursor` stays in a view of `len` cells.
+fn next_scroll_top(top: u16, cursor: u16, len: u16) -> u16 {
+    if cursor < top {
+        cursor
+    } else if top.saturating_add(len) <= cursor {
+        cursor + 1 - len
+    } else {
+        top
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::buffer::Buffer;
+    use ratatui::layout::Rect;
+    use ratatui::style::{Modifier, Style};
+    use ratatui::widgets::Widget;
+    use tui_textarea::CursorMove;
+
+    /// The scroll position has to follow the text area's own, which is private,
+    /// for the gutter signs and bracket highlights to line up with the text.
+    #[test]
+    fn scroll_follows_text_area() {
+        let (width, height) = (30, 10);
+        let mut buffer = QueryBuffer::new("test".to_string());
+        buffer.textarea = TextArea::new((0..150).map(|i| "x".repeat(i * 7 % 60)).collect());
+        buffer.textarea.set_line_number_style(Style::default());
+        let gutter = buffer.textarea.lines().len().to_string().len() + 2;
+        let mut seed = 7u64;
+        for _ in 0..2000 {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            let r = (seed >> 33) as u16;
+            let jump = CursorMove::Jump(r % 150, r / 7 % 70);
+            buffer.textarea.move_cursor(jump);
+            buffer.scroll_to_cursor(width, height);
+
+            let area = Rect::new(0, 0, width, height);
+            let mut rendered = Buffer::empty(area);
+            (&buffer.textarea).render(area, &mut rendered);
+            let (row, col) = buffer.textarea.cursor();
+            let x = (gutter + col - buffer.scroll.1 as usize) as u16;
+            let y = (row - buffer.scroll.0 as usize) as u16;
+            assert!(
+                rendered[(x, y)].modifier.contains(Modifier::REVERSED),
+                "cursor {:?} not at {:?} with scroll {:?}",
+                (row, col),
+                (x, y),
+                buffer.scroll
+            );
+        }
+    }
+}
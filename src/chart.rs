@@ -0,0 +1,405 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Rect},
+    style::{Color, Modifier, Style},
+    symbols::Marker,
+    text::Line,
+    widgets::{
+        Axis, Bar, BarChart, BarGroup, Block, Chart, Dataset, GraphType, LegendPosition, Widget,
+    },
+};
+
+use crate::app::{App, AppResult};
+use crate::custom_table::Table;
+use crate::stats::{histogram_query, is_numeric};
+
+/// Number of buckets line charts are aggregated into.
+const MAX_POINTS: usize = 500;
+/// Number of rows sampled for scatter plots.
+const SAMPLE_ROWS: usize = 1000;
+/// Number of categories shown by bar charts.
+const MAX_BARS: usize = 30;
+/// Number of bins of histograms.
+const BINS: usize = 30;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ChartKind {
+    #[default]
+    Line,
+    Bar,
+    Histogram,
+    Scatter,
+}
+
+impl ChartKind {
+    pub fn next(self) -> Self {
+        match self {
+            ChartKind::Line => ChartKind::Bar,
+            ChartKind::Bar => ChartKind::Histogram,
+            ChartKind::Histogram => ChartKind::Scatter,
+            ChartKind::Scatter => ChartKind::Line,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ChartKind::Line => "Line",
+            ChartKind::Bar => "Bar",
+            ChartKind::Histogram => "Histogram",
+            ChartKind::Scatter => "Scatter",
+        }
+    }
+}
+
+/// Columns of the result schema picked for a chart.
+#[derive(Debug, Clone, Default)]
+pub struct ChartConfig {
+    pub kind: ChartKind,
+    /// Column on the x axis, or the category of bar charts.
+    pub x: usize,
+    /// Columns plotted against `x`.
+    pub series: Vec<usize>,
+}
+
+impl ChartConfig {
+    /// Picks the selected column for x and the first other numeric column as series.
+    pub fn for_table(table: &Table, selected: usize) -> Self {
+        let series = table
+            .types
+            .iter()
+            .enumerate()
+            .find(|(i, t)| *i != selected && is_numeric(t))
+            .map(|(i, _)| vec![i])
+            .unwrap_or_default();
+        Self {
+            kind: ChartKind::default(),
+            x: selected,
+            series,
+        }
+    }
+
+    pub fn toggle_series(&mut self, column: usize) {
+        if let Some(pos) = self.series.iter().position(|&c| c == column) {
+            self.series.remove(pos);
+        } else {
+            self.series.push(column);
+        }
+    }
+}
+
+fn is_temporal(data_type: &str) -> bool {
+    data_type.starts_with("DATE") || data_type.starts_with("TIMESTAMP")
+}
+
+fn quote(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+fn parse(value: Option<&String>) -> Option<f64> {
+    value?.parse().ok()
+}
+
+fn format_number(value: f64) -> String {
+    if value.fract() == 0.0 && value.abs() < 1e15 {
+        format!("{value:.0}")
+    } else {
+        format!("{value:.2}")
+    }
+}
+
+fn bounds(values: impl Iterator<Item = f64>) -> [f64; 2] {
+    let (min, max) = values.fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), v| {
+        (lo.min(v), hi.max(v))
+    });
+    if !min.is_finite() || !max.is_finite() {
+        [0.0, 1.0]
+    } else if min == max {
+        [min - 1.0, max + 1.0]
+    } else {
+        [min, max]
+    }
+}
+
+/// Plot data of a chart, aggregated by DuckDB.
+#[derive(Debug, Clone, Default)]
+pub struct ChartData {
+    pub kind: ChartKind,
+    pub x_title: String,
+    pub x_bounds: [f64; 2],
+    pub x_labels: Vec<String>,
+    pub y_bounds: [f64; 2],
+    pub series: Vec<(String, Vec<(f64, f64)>)>,
+    pub bars: Vec<(String, f64)>,
+}
+
+impl ChartData {
+    /// Runs the aggregation for `config` over the results of the active tab,
+    /// read from its temp table when the tab is stored in one.
+    pub fn load(app: &App, config: &ChartConfig) -> AppResult<Self> {
+        let tab = app.tab();
+        let headers = &tab.table.headers;
+        let types = &tab.table.types;
+        let x_name = headers
+            .get(config.x)
+            .ok_or("No column selected for the x axis")?;
+        let x_type = types.get(config.x).map(String::as_str).unwrap_or_default();
+        let source = tab.source();
+        let x = quote(x_name);
+        let x_expr = if is_temporal(x_type) {
+            format!("epoch({x})")
+        } else {
+            format!("{x}::DOUBLE")
+        };
+        let series: Vec<&String> = config
+            .series
+            .iter()
+            .filter_map(|&i| headers.get(i))
+            .collect();
+
+        let mut data = Self {
+            kind: config.kind,
+            x_title: x_name.clone(),
+            ..Default::default()
+        };
+
+        match config.kind {
+            ChartKind::Line | ChartKind::Scatter => {
+                if series.is_empty() {
+                    return Err("Pick at least one series column".into());
+                }
+                let columns: Vec<String> = series
+                    .iter()
+                    .enumerate()
+                    .map(|(i, name)| format!("{}::DOUBLE AS s{i}", quote(name)))
+                    .collect();
+                let inner = format!("SELECT {x_expr} AS x, {} FROM {source}", columns.join(", "));
+                let sql = if config.kind == ChartKind::Line {
+                    let averages: Vec<String> = (0..series.len())
+                        .map(|i| format!("avg(s{i}) AS s{i}"))
+                        .collect();
+                    format!(
+                        "SELECT avg(x) AS x, {} FROM (SELECT *, ntile({MAX_POINTS}) OVER (ORDER BY x) AS bucket \
+                         FROM ({inner}) WHERE x IS NOT NULL) GROUP BY bucket ORDER BY x",
+                        averages.join(", ")
+                    )
+                } else {
+                    format!(
+                        "SELECT * FROM (SELECT * FROM ({inner}) WHERE x IS NOT NULL) \
+                         USING SAMPLE reservoir({SAMPLE_ROWS} ROWS)"
+                    )
+                };
+                let points = app.query_table(&sql)?;
+                data.series = series
+                    .iter()
+                    .enumerate()
+                    .map(|(i, name)| {
+                        let values = points
+                            .rows
+                            .iter()
+                            .filter_map(|row| Some((parse(row.first())?, parse(row.get(i + 1))?)))
+                            .collect();
+                        (name.to_string(), values)
+                    })
+                    .collect();
+
+                data.x_bounds = bounds(
+                    data.series
+                        .iter()
+                        .flat_map(|(_, points)| points.iter().map(|(x, _)| *x)),
+                );
+                // Labels match the bounds, which come from the bucketed points.
+                let [x_min, x_max] = data.x_bounds;
+                data.x_labels = if is_temporal(x_type) {
+                    let cast = if x_type == "DATE" {
+                        "DATE"
+                    } else {
+                        "TIMESTAMP"
+                    };
+                    let label = |epoch: f64| {
+                        format!("make_timestamp(({epoch} * 1e6)::BIGINT)::{cast}::VARCHAR")
+                    };
+                    let range =
+                        app.query_table(&format!("SELECT {}, {}", label(x_min), label(x_max)))?;
+                    range.rows.first().cloned().unwrap_or_default()
+                } else {
+                    vec![format_number(x_min), format_number(x_max)]
+                };
+                data.y_bounds = bounds(
+                    data.series
+                        .iter()
+                        .flat_map(|(_, points)| points.iter().map(|(_, y)| *y)),
+                );
+            }
+            ChartKind::Bar => {
+                let value = series
+                    .first()
+                    .map(|name| format!("sum({})::DOUBLE", quote(name)))
+                    .unwrap_or_else(|| "count(*)::DOUBLE".to_string());
+                let bars = app.query_table(&format!(
+                    "SELECT {x}::VARCHAR AS label, {value} AS v FROM {source} \
+                     GROUP BY label ORDER BY v DESC LIMIT {MAX_BARS}"
+                ))?;
+                data.bars = bars
+                    .rows
+                    .iter()
+                    .filter_map(|row| Some((row.first()?.clone(), parse(row.get(1))?)))
+                    .collect();
+            }
+            ChartKind::Histogram => {
+                let bins = app.query_table(&histogram_query(&x_expr, &source, BINS))?;
+                data.bars = bins
+                    .rows
+                    .iter()
+                    .filter_map(|row| {
+                        let bin = parse(row.first())?;
+                        let (lo, hi) = (parse(row.get(2))?, parse(row.get(3))?);
+                        let start = lo + bin * (hi - lo) / BINS as f64;
+                        Some((format_number(start), parse(row.get(1))?))
+                    })
+                    .collect();
+            }
+        }
+
+        Ok(data)
+    }
+}
+
+/// Widget drawing [`ChartData`] with ratatui's chart widgets.
+pub struct ChartView<'a> {
+    data: &'a ChartData,
+    block: Option<Block<'a>>,
+    style: Style,
+    axis_style: Style,
+    palette: &'a [Color],
+}
+
+impl<'a> ChartView<'a> {
+    pub fn new(data: &'a ChartData) -> Self {
+        Self {
+            data,
+            block: None,
+            style: Style::default(),
+            axis_style: Style::default(),
+            palette: &[Color::Reset],
+        }
+    }
+
+    pub fn block(mut self, block: Block<'a>) -> Self {
+        self.block = Some(block);
+        self
+    }
+
+    pub fn style(mut self, style: Style) -> Self {
+        self.style = style;
+        self
+    }
+
+    pub fn axis_style(mut self, style: Style) -> Self {
+        self.axis_style = style;
+        self
+    }
+
+    /// Colors assigned to the series in order.
+    pub fn palette(mut self, palette: &'a [Color]) -> Self {
+        self.palette = palette;
+        self
+    }
+
+    fn color(&self, i: usize) -> Color {
+        self.palette[i % self.palette.len()]
+    }
+}
+
+impl Widget for ChartView<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let block = self.block.clone().unwrap_or_default();
+        match self.data.kind {
+            ChartKind::Line | ChartKind::Scatter => {
+                let graph_type = if self.data.kind == ChartKind::Line {
+                    GraphType::Line
+                } else {
+                    GraphType::Scatter
+                };
+                let datasets = self
+                    .data
+                    .series
+                    .iter()
+                    .enumerate()
+                    .map(|(i, (name, points))| {
+                        Dataset::default()
+                            .name(name.clone())
+                            .marker(Marker::Braille)
+                            .graph_type(graph_type)
+                            .style(Style::default().fg(self.color(i)))
+                            .data(points)
+                    })
+                    .collect();
+                let [y_min, y_max] = self.data.y_bounds;
+                Chart::new(datasets)
+                    .block(block)
+                    .style(self.style)
+                    .x_axis(
+                        Axis::default()
+                            .title(self.data.x_title.clone())
+                            .style(self.axis_style)
+                            .bounds(self.data.x_bounds)
+                            .labels(self.data.x_labels.clone()),
+                    )
+                    .y_axis(
+                        Axis::default()
+                            .style(self.axis_style)
+                            .bounds(self.data.y_bounds)
+                            .labels([
+                                format_number(y_min),
+                                format_number((y_min + y_max) / 2.0),
+                                format_number(y_max),
+                            ]),
+                    )
+                    .legend_position(Some(LegendPosition::TopRight))
+                    .hidden_legend_constraints((
+                        Constraint::Percentage(50),
+                        Constraint::Percentage(50),
+                    ))
+                    .render(area, buf);
+            }
+            ChartKind::Bar | ChartKind::Histogram => {
+                // Bar heights are integers, so values are scaled against the largest one.
+                let max = self
+                    .data
+                    .bars
+                    .iter()
+                    .map(|(_, v)| v.abs())
+                    .fold(0.0, f64::max)
+                    .max(f64::MIN_POSITIVE);
+                let bars: Vec<Bar> = self
+                    .data
+                    .bars
+                    .iter()
+                    .map(|(label, value)| {
+                        Bar::default()
+                            .value((value.max(0.0) / max * 1000.0).round() as u64)
+                            .label(Line::from(label.clone()))
+                            .text_value(format_number(*value))
+                    })
+                    .collect();
+                let inner_width = block.inner(area).width;
+                let count = bars.len().max(1) as u16;
+                let bar_width = (inner_width / count).saturating_sub(1).clamp(1, 12);
+                BarChart::default()
+                    .block(block)
+                    .style(self.style)
+                    .data(BarGroup::default().bars(&bars))
+                    .bar_width(bar_width)
+                    .bar_gap(1)
+                    .bar_style(Style::default().fg(self.color(0)))
+                    .value_style(
+                        Style::default()
+                            .fg(self.color(0))
+                            .add_modifier(Modifier::REVERSED),
+                    )
+                    .label_style(self.axis_style)
+                    .render(area, buf);
+            }
+        }
+    }
+}
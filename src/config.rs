@@ -0,0 +1,110 @@
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use crate::format::FormatOptions;
+use crate::keymap::Keymap;
+use crate::variables;
+
+/// Settings read from the `config` file in the configuration directory.
+///
+/// The file has one `key = value` setting per line; lines starting with `#`
+/// are comments.
+#[derive(Debug, Clone, Default)]
+pub struct Config {
+    pub format: FormatOptions,
+    pub keymap: Keymap,
+    /// Directory of the query library, `library` in the configuration directory by default.
+    pub library_dir: Option<PathBuf>,
+    /// Values of `${NAME}` variables in queries, set with `vars.NAME` or `--var`.
+    pub variables: HashMap<String, String>,
+}
+
+impl Config {
+    /// The configuration directory, `$XDG_CONFIG_HOME/quackmire` or `~/.config/quackmire`.
+    pub fn dir() -> Option<PathBuf> {
+        env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+            .map(|dir| dir.join("quackmire"))
+    }
+
+    /// Directory the query library is stored in.
+    pub fn library_dir(&self) -> Option<PathBuf> {
+        self.library_dir
+            .clone()
+            .or_else(|| Self::dir().map(|dir| dir.join("library")))
+    }
+
+    /// Loads the config file, if there is one.
+    ///
+    /// Settings that can't be read are skipped and returned as warnings.
+    pub fn load() -> (Self, Vec<String>) {
+        let mut config = Self::default();
+        let Some(path) = Self::dir().map(|dir| dir.join("config")) else {
+            return (config, Vec::new());
+        };
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return (config, Vec::new()),
+            Err(e) => return (config, vec![format!("{}: {e}", path.display())]),
+        };
+
+        let mut warnings = Vec::new();
+        for (number, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let result = match line.split_once('=') {
+                Some((key, value)) => config.set(key.trim(), value.trim()),
+                None => Err("expected key = value".to_string()),
+            };
+            if let Err(e) = result {
+                warnings.push(format!("{}:{}: {e}", path.display(), number + 1));
+            }
+        }
+        (config, warnings)
+    }
+
+    /// Applies a single setting.
+    fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        let number = |value: &str| {
+            value
+                .parse::<usize>()
+                .map_err(|_| format!("{key}: expected a number, got {value:?}"))
+        };
+        match key {
+            "format.keyword_case" => {
+                self.format.keyword_case = value.parse().map_err(|e| format!("{key}: {e}"))?
+            }
+            "format.indent" => self.format.indent = number(value)?,
+            "format.width" => self.format.width = number(value)?,
+            "library.dir" => {
+                self.library_dir = Some(match value.strip_prefix("~/") {
+                    Some(rest) => env::var_os("HOME")
+                        .map(PathBuf::from)
+                        .ok_or_else(|| format!("{key}: HOME is not set"))?
+                        .join(rest),
+                    None => PathBuf::from(value),
+                })
+            }
+            _ if key.starts_with("vars.") => {
+                let name = &key["vars.".len()..];
+                if !variables::is_name(name) {
+                    return Err(format!("{key}: invalid variable name"));
+                }
+                self.variables.insert(name.to_string(), value.to_string());
+            }
+            _ if key.starts_with("keys.") => self
+                .keymap
+                .bind(&key["keys.".len()..], value)
+                .map_err(|e| format!("{key}: {e}"))?,
+            _ => return Err(format!("unknown setting {key}")),
+        }
+        Ok(())
+    }
+}
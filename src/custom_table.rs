@@ -1,15 +1,29 @@
+use duckdb::arrow::array::RecordBatch;
+use duckdb::arrow::error::ArrowError;
+use duckdb::arrow::util::display::{ArrayFormatter, FormatOptions};
 use ratatui::{
     buffer::Buffer,
     layout::Rect,
-    style::Style,
+    style::{Modifier, Style},
     widgets::{Block, StatefulWidget, Widget},
 };
 use unicode_width::UnicodeWidthStr;
 
+use crate::app::type_name;
+
 #[derive(Debug, Clone)]
 pub struct TableState {
     pub offset_x: u16,
-    pub offset_y: u16,
+    /// Index of the first row shown.
+    pub offset_y: usize,
+    /// Index of the selected row.
+    pub selected: usize,
+    /// Index of the selected column.
+    pub selected_col: usize,
+    /// Number of rows that fit in the last rendered area.
+    pub visible_rows: u16,
+    /// Width of the last rendered area.
+    pub visible_width: u16,
 }
 
 impl Default for TableState {
@@ -17,6 +31,47 @@ impl Default for TableState {
         Self {
             offset_x: 0,
             offset_y: 0,
+            selected: 0,
+            selected_col: 0,
+            visible_rows: 0,
+            visible_width: 0,
+        }
+    }
+}
+
+impl TableState {
+    /// Adjusts the vertical offset so the selected row is visible.
+    pub fn scroll_to_selected(&mut self) {
+        let visible_rows = self.visible_rows as usize;
+        if self.selected < self.offset_y {
+            self.offset_y = self.selected;
+        } else if visible_rows > 0 && self.selected >= self.offset_y + visible_rows {
+            self.offset_y = self.selected - visible_rows + 1;
+        }
+    }
+
+    /// Adjusts the horizontal offset so the selected column is visible.
+    pub fn scroll_to_column(&mut self, widths: &[u16]) {
+        let Some(&width) = widths.get(self.selected_col) else {
+            return;
+        };
+        let starts: Vec<u16> = widths[..=self.selected_col]
+            .iter()
+            .scan(0, |start, w| {
+                let current = *start;
+                *start += w + 1;
+                Some(current)
+            })
+            .collect();
+        let start = starts[self.selected_col];
+        let end = start + width;
+        if start < self.offset_x {
+            self.offset_x = start;
+        } else if self.visible_width > 0 && end > self.offset_x + self.visible_width {
+            self.offset_x = starts
+                .into_iter()
+                .find(|s| end - s <= self.visible_width)
+                .unwrap_or(start);
         }
     }
 }
@@ -25,10 +80,24 @@ impl Default for TableState {
 pub struct Table {
     pub headers: Vec<String>,
     pub rows: Vec<Vec<String>>,
+    pub types: Vec<String>,
     pub widths: Vec<u16>,
     pub block: Option<Block<'static>>,
     pub style: Style,
     pub header_style: Style,
+    pub selected_style: Style,
+    /// Per-row styles, patched over `style`.
+    pub row_styles: Vec<Style>,
+    /// Per-cell styles, patched over the row style.
+    pub cell_styles: Vec<Vec<Style>>,
+    /// Whether columns are drawn in box-drawing borders.
+    pub borders: bool,
+    /// Whether row numbers are shown in a gutter.
+    pub row_numbers: bool,
+    /// Whether column types are shown under the headers.
+    pub show_types: bool,
+    /// Style of row numbers, column types and borders.
+    pub muted_style: Style,
 }
 
 impl Table {
@@ -36,10 +105,18 @@ impl Table {
         Self {
             headers: vec![],
             rows: vec![],
+            types: vec![],
             widths: vec![],
             block: None,
             style: Style::default(),
             header_style: Style::default(),
+            selected_style: Style::default(),
+            row_styles: vec![],
+            cell_styles: vec![],
+            borders: false,
+            row_numbers: false,
+            show_types: false,
+            muted_style: Style::default(),
         }
     }
 
@@ -69,13 +146,73 @@ impl Table {
         Self {
             headers,
             rows,
+            types: vec![],
             widths,
             block: None,
             style: Style::default(),
             header_style: Style::default(),
+            selected_style: Style::default(),
+            row_styles: vec![],
+            cell_styles: vec![],
+            borders: false,
+            row_numbers: false,
+            show_types: false,
+            muted_style: Style::default(),
         }
     }
 
+    /// Formats Arrow record batches into a table with one row per record.
+    pub fn from_batches(batches: &[RecordBatch]) -> Result<Self, ArrowError> {
+        let options = FormatOptions::default();
+
+        let Some(first) = batches.first() else {
+            return Ok(Self::default());
+        };
+        let schema = first.schema();
+
+        let headers: Vec<String> = schema.fields.iter().map(|f| f.name().clone()).collect();
+        let types: Vec<String> = schema
+            .fields
+            .iter()
+            .map(|f| type_name(f.data_type()))
+            .collect();
+
+        let mut rows: Vec<Vec<String>> = Vec::new();
+        for batch in batches {
+            let formatters = batch
+                .columns()
+                .iter()
+                .map(|c| ArrayFormatter::try_new(c.as_ref(), &options))
+                .collect::<Result<Vec<_>, _>>()?;
+            rows.extend((0..batch.num_rows()).map(|row| {
+                formatters
+                    .iter()
+                    .map(|formatter| formatter.value(row).to_string())
+                    .collect()
+            }));
+        }
+
+        Ok(Self::new(headers, rows).types(types))
+    }
+
+    /// Widths of the columns, wide enough for their types when `types` are shown.
+    pub fn column_widths(&self, types: bool) -> Vec<u16> {
+        self.widths
+            .iter()
+            .enumerate()
+            .map(|(i, &width)| match self.types.get(i).filter(|_| types) {
+                Some(type_name) => width.max(type_name.width() as u16),
+                None => width,
+            })
+            .collect()
+    }
+
+    /// Returns the value of the named column in `row`.
+    pub fn value(&self, row: usize, column: &str) -> Option<&str> {
+        let col = self.headers.iter().position(|h| h == column)?;
+        self.rows.get(row)?.get(col).map(String::as_str)
+    }
+
     pub fn block(mut self, block: Block<'static>) -> Self {
         self.block = Some(block);
         self
@@ -90,13 +227,66 @@ impl Table {
         self.header_style = style;
         self
     }
+
+    pub fn selected_style(mut self, style: Style) -> Self {
+        self.selected_style = style;
+        self
+    }
+
+    pub fn types(mut self, types: Vec<String>) -> Self {
+        self.types = types;
+        self
+    }
+
+    pub fn row_styles(mut self, styles: Vec<Style>) -> Self {
+        self.row_styles = styles;
+        self
+    }
+
+    pub fn cell_styles(mut self, styles: Vec<Vec<Style>>) -> Self {
+        self.cell_styles = styles;
+        self
+    }
+
+    pub fn borders(mut self, borders: bool) -> Self {
+        self.borders = borders;
+        self
+    }
+
+    pub fn row_numbers(mut self, row_numbers: bool) -> Self {
+        self.row_numbers = row_numbers;
+        self
+    }
+
+    pub fn show_types(mut self, show_types: bool) -> Self {
+        self.show_types = show_types;
+        self
+    }
+
+    pub fn muted_style(mut self, style: Style) -> Self {
+        self.muted_style = style;
+        self
+    }
+}
+
+/// Width of a gutter holding the numbers of `rows` rows and a space.
+pub fn gutter_width(rows: usize) -> u16 {
+    rows.max(1).to_string().len() as u16 + 1
 }
 
 impl StatefulWidget for Table {
     type State = TableState;
 
     fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
-        let table_area = match self.block {
+        (&self).render(area, buf, state);
+    }
+}
+
+impl StatefulWidget for &Table {
+    type State = TableState;
+
+    fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+        let table_area = match &self.block {
             Some(b) => {
                 let inner_area = b.inner(area);
                 b.render(area, buf);
@@ -111,14 +301,28 @@ impl StatefulWidget for Table {
 
         buf.set_style(table_area, self.style);
 
-        let visible_width = table_area.width;
-        let visible_height = table_area.height;
+        let gutter = if self.row_numbers {
+            gutter_width(self.rows.len())
+        } else {
+            0
+        };
+        let border = u16::from(self.borders);
+        // Top border, headers, types and the rule under them.
+        let header_height = 1 + u16::from(self.show_types) + 2 * border;
+        let widths = self.column_widths(self.show_types);
+        let visible_width = table_area.width.saturating_sub(gutter + border);
+        let visible_height = table_area
+            .height
+            .saturating_sub(header_height + border)
+            .max(1);
+        state.visible_rows = visible_height;
+        state.visible_width = visible_width;
 
         // Calculate visible columns
         let mut visible_columns = Vec::new();
         let mut cumulative_width = 0;
         let mut start_col = 0;
-        for (i, &width) in self.widths.iter().enumerate() {
+        for (i, &width) in widths.iter().enumerate() {
             if cumulative_width >= state.offset_x {
                 if start_col == 0 {
                     start_col = i;
@@ -133,33 +337,139 @@ impl StatefulWidget for Table {
             }
         }
 
+        let left = table_area.left() + gutter + border;
+        // Writes text clipped to the table area.
+        let put = |buf: &mut Buffer, x: u16, y: u16, text: &str, width: usize, style: Style| {
+            if x < table_area.right() && y < table_area.bottom() {
+                let width = width.min((table_area.right() - x) as usize);
+                buf.set_stringn(x, y, text, width, style);
+            }
+        };
+        // Draws a horizontal border from `chars`: the left end, the line, the
+        // crossing and the right end.
+        let rule = |buf: &mut Buffer, y: u16, chars: [&str; 4]| {
+            let mut x = left - 1;
+            put(buf, x, y, chars[0], 1, self.muted_style);
+            for (n, (_, width)) in visible_columns.iter().enumerate() {
+                let end = if n + 1 == visible_columns.len() {
+                    chars[3]
+                } else {
+                    chars[2]
+                };
+                let line = format!("{}{end}", chars[1].repeat(*width as usize));
+                put(buf, x + 1, y, &line, *width as usize + 1, self.muted_style);
+                x += width + 1;
+            }
+        };
+        // Draws the bars between columns on a line.
+        let bars = |buf: &mut Buffer, y: u16| {
+            if self.borders {
+                put(buf, left - 1, y, "│", 1, self.muted_style);
+                let mut x = left;
+                for (_, width) in visible_columns.iter() {
+                    put(buf, x + width, y, "│", 1, self.muted_style);
+                    x += width + 1;
+                }
+            }
+        };
+
         // Render headers
         let mut y = table_area.top();
-        let mut x = table_area.left();
+        if self.borders {
+            rule(buf, y, ["┌", "─", "┬", "┐"]);
+            y += 1;
+        }
+        let mut x = left;
         for (i, width) in visible_columns.iter() {
             if let Some(header) = self.headers.get(*i) {
-                buf.set_string(x, y, header, self.header_style);
+                put(buf, x, y, header, *width as usize, self.header_style);
             }
             x += width + 1;
         }
+        bars(buf, y);
+        if self.show_types {
+            y += 1;
+            x = left;
+            for (i, width) in visible_columns.iter() {
+                if let Some(type_name) = self.types.get(*i) {
+                    put(buf, x, y, type_name, *width as usize, self.muted_style);
+                }
+                x += width + 1;
+            }
+            bars(buf, y);
+        }
+        if self.borders {
+            y += 1;
+            rule(buf, y, ["├", "─", "┼", "┤"]);
+        }
 
         // Render rows
-        if !self.rows.is_empty() {
-            for row in self
-                .rows
-                .iter()
-                .skip(state.offset_y as usize)
-                .take(visible_height as usize - 1)
-            {
-                y += 1;
-                x = table_area.left();
-                for (i, width) in visible_columns.iter() {
-                    if let Some(cell) = row.get(*i) {
-                        buf.set_string(x, y, cell, self.style);
-                    }
-                    x += width + 1;
+        for (index, row) in self
+            .rows
+            .iter()
+            .enumerate()
+            .skip(state.offset_y)
+            .take(visible_height as usize)
+        {
+            y += 1;
+            if y >= table_area.bottom() {
+                break;
+            }
+            x = left;
+            let row_style = self
+                .style
+                .patch(self.row_styles.get(index).copied().unwrap_or_default());
+            buf.set_style(
+                Rect::new(table_area.left(), y, table_area.width, 1),
+                row_style,
+            );
+            if self.row_numbers {
+                let number = format!("{:>1$}", index + 1, gutter as usize - 1);
+                put(
+                    buf,
+                    table_area.left(),
+                    y,
+                    &number,
+                    gutter as usize,
+                    self.muted_style,
+                );
+            }
+            let mut selected_cell = None;
+            for (i, width) in visible_columns.iter() {
+                if let Some(cell) = row.get(*i) {
+                    let cell_style = self
+                        .cell_styles
+                        .get(index)
+                        .and_then(|styles| styles.get(*i))
+                        .copied()
+                        .unwrap_or_default();
+                    put(buf, x, y, cell, *width as usize, row_style);
+                    buf.set_style(
+                        Rect::new(x, y, *width, 1).intersection(table_area),
+                        cell_style,
+                    );
+                }
+                if *i == state.selected_col {
+                    selected_cell = Some(Rect::new(x, y, *width, 1).intersection(table_area));
+                }
+                x += width + 1;
+            }
+            bars(buf, y);
+            if index == state.selected {
+                buf.set_style(
+                    Rect::new(table_area.left(), y, table_area.width, 1),
+                    self.selected_style,
+                );
+                if let Some(cell_area) = selected_cell {
+                    buf.set_style(
+                        cell_area,
+                        self.selected_style.add_modifier(Modifier::REVERSED),
+                    );
                 }
             }
         }
+        if self.borders && y + 1 < table_area.bottom() {
+            rule(buf, y + 1, ["└", "─", "┴", "┘"]);
+        }
     }
 }
@@ -0,0 +1,141 @@
+/// A DuckDB error split into its parts.
+///
+/// Parser and binder errors end with a `LINE n: ...` excerpt of the query and a
+/// caret under the offending position, and may carry "Did you mean" hints.
+#[derive(Debug, Clone, Default)]
+pub struct Diagnostic {
+    /// Error class, such as `Parser Error` or `Binder Error`.
+    pub kind: String,
+    pub message: String,
+    /// Remaining lines of the error, without the query excerpt.
+    pub details: Vec<String>,
+    /// Row and column of the offending position in the query, in characters.
+    pub position: Option<(usize, usize)>,
+    /// Text at the offending position that the suggestions would replace.
+    pub token: Option<String>,
+    pub suggestions: Vec<String>,
+    /// The error as reported by DuckDB.
+    pub raw: String,
+}
+
+impl Diagnostic {
+    /// Parses the error `raw` raised while running `query`.
+    pub fn parse(raw: &str, query: &str) -> Self {
+        let raw = raw.trim();
+        let body = raw.strip_prefix("Error: ").unwrap_or(raw);
+        let mut lines = body.lines();
+        let first = lines.next().unwrap_or_default();
+        let (kind, message) = match first.split_once(": ") {
+            Some((kind, message)) if kind.ends_with("Error") => (kind, message),
+            _ => ("Error", first),
+        };
+
+        let mut diagnostic = Self {
+            kind: kind.to_string(),
+            message: message.to_string(),
+            raw: raw.to_string(),
+            ..Default::default()
+        };
+
+        let rest: Vec<&str> = lines.collect();
+        let mut i = 0;
+        while i < rest.len() {
+            let line = rest[i];
+            if let Some(position) = excerpt_position(line, rest.get(i + 1).copied(), query) {
+                diagnostic.position = Some(position);
+                i += 2;
+                continue;
+            }
+            if line.starts_with("Did you mean") || line.starts_with("Candidate bindings") {
+                diagnostic.suggestions.extend(quoted(line));
+            }
+            if !line.trim().is_empty() {
+                diagnostic.details.push(line.to_string());
+            }
+            i += 1;
+        }
+
+        diagnostic.token = quoted(message)
+            .into_iter()
+            .next()
+            .filter(|_| message.contains("at or near"))
+            .or_else(|| {
+                let (row, col) = diagnostic.position?;
+                let token: String = query
+                    .lines()
+                    .nth(row)?
+                    .chars()
+                    .skip(col)
+                    .take_while(|c| c.is_alphanumeric() || matches!(c, '_' | '.' | '"'))
+                    .collect();
+                (!token.is_empty()).then_some(token)
+            });
+
+        diagnostic
+    }
+}
+
+/// Returns the quoted names in `line`.
+fn quoted(line: &str) -> Vec<String> {
+    line.split('"')
+        .skip(1)
+        .step_by(2)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Locates the caret under a `LINE n: ...` excerpt in `query`.
+fn excerpt_position(line: &str, caret: Option<&str>, query: &str) -> Option<(usize, usize)> {
+    let rest = line.strip_prefix("LINE ")?;
+    let (number, excerpt) = rest.split_once(": ")?;
+    let row = number.trim().parse::<usize>().ok()?.checked_sub(1)?;
+    let caret = caret?;
+    let prefix = line.chars().count() - excerpt.chars().count();
+    let offset = caret.chars().position(|c| c == '^')?.checked_sub(prefix)?;
+
+    // Long lines are cut down to the part around the error and marked with "...".
+    let (excerpt, offset) = match excerpt.strip_prefix("...") {
+        Some(excerpt) => (excerpt, offset.saturating_sub(3)),
+        None => (excerpt, offset),
+    };
+    let excerpt = excerpt.strip_suffix("...").unwrap_or(excerpt);
+    let start = query
+        .lines()
+        .nth(row)
+        .and_then(|source| {
+            source
+                .find(excerpt)
+                .map(|byte| source[..byte].chars().count())
+        })
+        .unwrap_or(0);
+    Some((row, start + offset))
+}
+
+/// Returns the byte offset in `text` of a row and column in characters.
+pub fn offset_of(text: &str, (row, col): (usize, usize)) -> Option<usize> {
+    let mut start = 0;
+    for (i, line) in text.split('\n').enumerate() {
+        if i == row {
+            let byte = line
+                .char_indices()
+                .nth(col)
+                .map_or(line.len(), |(byte, _)| byte);
+            return Some(start + byte);
+        }
+        start += line.len() + 1;
+    }
+    None
+}
+
+/// Returns the row and column in characters of a byte offset in `text`.
+pub fn position_of(text: &str, offset: usize) -> (usize, usize) {
+    let mut end = offset.min(text.len());
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    let before = &text[..end];
+    let row = before.matches('\n').count();
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    (row, before[line_start..].chars().count())
+}
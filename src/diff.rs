@@ -0,0 +1,314 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Layout, Rect},
+    style::{Style, Stylize},
+    widgets::{Block, Borders, StatefulWidget, Widget},
+};
+
+use crate::app::{App, AppResult};
+use crate::custom_table::{Table, TableState};
+use crate::ui::{BG, FG, GRAY, GREEN, PURPLE, RED, YELLOW};
+
+/// How a row differs between the two result sets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Change {
+    Added,
+    Removed,
+    Changed,
+}
+
+impl Change {
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "added" => Some(Self::Added),
+            "removed" => Some(Self::Removed),
+            "changed" => Some(Self::Changed),
+            _ => None,
+        }
+    }
+}
+
+/// Rows that differ between two result sets, aligned side by side.
+#[derive(Debug, Clone)]
+pub struct Diff {
+    pub left_name: String,
+    pub right_name: String,
+    /// Column the rows were matched on, or `None` for a full-row `EXCEPT`.
+    pub key: Option<String>,
+    pub headers: Vec<String>,
+    pub widths: Vec<u16>,
+    pub changes: Vec<Change>,
+    /// The two sides, built once when the diff is loaded.
+    pub left: Table,
+    pub right: Table,
+}
+
+fn quote(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+impl Diff {
+    /// Compares `left` and `right`, relations usable in a `FROM` clause.
+    ///
+    /// With a `key`, it has to be unique on both sides.
+    pub fn load(
+        app: &App,
+        (left_name, left): (String, &str),
+        (right_name, right): (String, &str),
+        key: Option<String>,
+    ) -> AppResult<Self> {
+        let columns = |source: &str| -> AppResult<Vec<String>> {
+            let describe = app.query_table(&format!("DESCRIBE SELECT * FROM {source}"))?;
+            Ok((0..describe.rows.len())
+                .filter_map(|row| describe.value(row, "column_name").map(str::to_string))
+                .collect())
+        };
+        let headers = columns(left)?;
+        if headers != columns(right)? {
+            return Err(format!("{left_name} and {right_name} have different columns").into());
+        }
+        if let Some(key) = &key {
+            if !headers.contains(key) {
+                return Err(format!("Key column {key} not found").into());
+            }
+            for (name, source) in [(&left_name, left), (&right_name, right)] {
+                let duplicates = app.query_table(&format!(
+                    "SELECT {0}::VARCHAR, count(*) FROM {source} GROUP BY {0} \
+                     HAVING count(*) > 1 LIMIT 1",
+                    quote(key)
+                ))?;
+                if let Some(row) = duplicates.rows.first() {
+                    return Err(format!(
+                        "{key} is not unique in {name}: {} appears {} times",
+                        row.first()
+                            .filter(|value| !value.is_empty())
+                            .map_or("NULL", |v| v),
+                        row.get(1).map_or("", |n| n)
+                    )
+                    .into());
+                }
+            }
+        }
+
+        let width = headers.len();
+        let mut changes = Vec::new();
+        let mut left_rows = Vec::new();
+        let mut right_rows = Vec::new();
+        let mut changed = Vec::new();
+
+        if let Some(key) = &key {
+            let key = quote(key);
+            // Marks the rows of each side, under a name no column has.
+            let mut present = "__present".to_string();
+            while headers.iter().any(|h| h.eq_ignore_ascii_case(&present)) {
+                present.push('_');
+            }
+            let present = quote(&present);
+            let side = |alias: &str| {
+                (0..width)
+                    .map(|i| format!("{alias}.{} AS {alias}{i}", quote(&headers[i])))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            };
+            let distinct: Vec<String> = headers
+                .iter()
+                .map(|h| format!("l.{0} IS DISTINCT FROM r.{0}", quote(h)))
+                .collect();
+            let table = app.query_table(&format!(
+                "SELECT CASE WHEN l.{present} IS NULL THEN 'added' \
+                 WHEN r.{present} IS NULL THEN 'removed' ELSE 'changed' END AS __change, \
+                 {}, {}, {} \
+                 FROM (SELECT *, true AS {present} FROM {left}) l \
+                 FULL OUTER JOIN (SELECT *, true AS {present} FROM {right}) r \
+                 ON l.{key} IS NOT DISTINCT FROM r.{key} \
+                 WHERE l.{present} IS NULL OR r.{present} IS NULL OR {} \
+                 ORDER BY coalesce(l.{key}, r.{key})",
+                side("l"),
+                side("r"),
+                distinct
+                    .iter()
+                    .enumerate()
+                    .map(|(i, d)| format!("{d} AS d{i}"))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                distinct.join(" OR "),
+            ))?;
+            for row in table.rows {
+                let Some(change) = row.first().and_then(|c| Change::parse(c)) else {
+                    continue;
+                };
+                let blank = vec![String::new(); width];
+                left_rows.push(match change {
+                    Change::Added => blank.clone(),
+                    _ => row[1..=width].to_vec(),
+                });
+                right_rows.push(match change {
+                    Change::Removed => blank,
+                    _ => row[width + 1..=2 * width].to_vec(),
+                });
+                changed.push(
+                    row[2 * width + 1..]
+                        .iter()
+                        .map(|d| change == Change::Changed && d == "true")
+                        .collect(),
+                );
+                changes.push(change);
+            }
+        } else {
+            let table = app.query_table(&format!(
+                "SELECT 'removed' AS __change, * FROM \
+                 (SELECT * FROM {left} EXCEPT ALL SELECT * FROM {right}) \
+                 UNION ALL \
+                 SELECT 'added' AS __change, * FROM \
+                 (SELECT * FROM {right} EXCEPT ALL SELECT * FROM {left})"
+            ))?;
+            for row in table.rows {
+                let Some(change) = row.first().and_then(|c| Change::parse(c)) else {
+                    continue;
+                };
+                let cells = row[1..].to_vec();
+                let blank = vec![String::new(); width];
+                if change == Change::Added {
+                    left_rows.push(blank);
+                    right_rows.push(cells);
+                } else {
+                    left_rows.push(cells);
+                    right_rows.push(blank);
+                }
+                changed.push(vec![false; width]);
+                changes.push(change);
+            }
+        }
+
+        let mut left = side(
+            &left_name,
+            &headers,
+            left_rows,
+            &changes,
+            &changed,
+            Change::Removed,
+        );
+        let mut right = side(
+            &right_name,
+            &headers,
+            right_rows,
+            &changes,
+            &changed,
+            Change::Added,
+        );
+        let widths: Vec<u16> = left
+            .widths
+            .iter()
+            .zip(&right.widths)
+            .map(|(l, r)| *l.max(r))
+            .collect();
+        left.widths = widths.clone();
+        right.widths = widths.clone();
+
+        Ok(Self {
+            left_name,
+            right_name,
+            key,
+            headers,
+            widths,
+            changes,
+            left,
+            right,
+        })
+    }
+
+    /// Number of rows with the given change.
+    pub fn count(&self, change: Change) -> usize {
+        self.changes.iter().filter(|c| **c == change).count()
+    }
+}
+
+/// Builds the table of one side of a diff, highlighting the rows only it has
+/// (`row_change`) and the cells that differ.
+fn side(
+    name: &str,
+    headers: &[String],
+    rows: Vec<Vec<String>>,
+    changes: &[Change],
+    changed: &[Vec<bool>],
+    row_change: Change,
+) -> Table {
+    let row_styles = changes
+        .iter()
+        .map(|&change| match change {
+            Change::Added if row_change == Change::Added => Style::default().fg(GREEN),
+            Change::Removed if row_change == Change::Removed => Style::default().fg(RED),
+            _ => Style::default(),
+        })
+        .collect();
+    let cell_styles = changed
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|&changed| {
+                    if changed {
+                        Style::default().fg(BG).bg(YELLOW)
+                    } else {
+                        Style::default()
+                    }
+                })
+                .collect()
+        })
+        .collect();
+    Table::new(headers.to_vec(), rows)
+        .block(
+            Block::default()
+                .borders(Borders::TOP)
+                .title(name.to_string()),
+        )
+        .style(Style::default().fg(FG).bg(BG))
+        .header_style(Style::default().fg(PURPLE).bold())
+        .selected_style(Style::default().bg(GRAY))
+        .row_styles(row_styles)
+        .cell_styles(cell_styles)
+}
+
+/// Widget showing the two sides of a [`Diff`] next to each other.
+pub struct DiffView<'a> {
+    diff: &'a Diff,
+    block: Option<Block<'static>>,
+}
+
+impl<'a> DiffView<'a> {
+    pub fn new(diff: &'a Diff) -> Self {
+        Self { diff, block: None }
+    }
+
+    pub fn block(mut self, block: Block<'static>) -> Self {
+        self.block = Some(block);
+        self
+    }
+}
+
+impl StatefulWidget for DiffView<'_> {
+    type State = TableState;
+
+    fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+        let area = match &self.block {
+            Some(b) => {
+                let inner_area = b.inner(area);
+                b.clone().render(area, buf);
+                inner_area
+            }
+            None => area,
+        };
+
+        if area.width < 3 || area.height < 3 {
+            return;
+        }
+
+        buf.set_style(area, Style::default().fg(FG).bg(BG));
+
+        let [left, right] =
+            Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)])
+                .spacing(1)
+                .areas(area);
+        (&self.diff.left).render(left, buf, state);
+        (&self.diff.right).render(right, buf, state);
+    }
+}
@@ -0,0 +1,195 @@
+use std::path::PathBuf;
+
+use crate::output::Mode;
+
+/// A meta-command of the DuckDB CLI, such as `.tables`, written on a line of its own.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DotCommand {
+    /// Lists the tables and views whose name matches a `LIKE` pattern.
+    Tables(Option<String>),
+    /// Shows the `CREATE` statements of the tables matching a `LIKE` pattern.
+    Schema(Option<String>),
+    Databases,
+    /// Runs the SQL and dot-commands of a file.
+    Read(PathBuf),
+    /// Sets the output mode, or shows it.
+    Mode(Option<Mode>),
+    /// Turns the run time report on or off.
+    Timer(bool),
+    /// Writes results to a file, or stops writing them.
+    Output(Option<PathBuf>),
+    /// Opens a database file, or a new in-memory database.
+    Open(Option<PathBuf>),
+    /// Limits the number of rows kept from results, or shows the limit.
+    MaxRows(Option<usize>),
+}
+
+impl DotCommand {
+    /// Parses a line starting with `.`.
+    ///
+    /// Arguments are separated by spaces and can be quoted with `'` or `"`.
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let words = split_arguments(line.trim().strip_prefix('.').unwrap_or(line));
+        let invalid = || {
+            format!("unknown command or invalid arguments: \"{}\". Supported: .databases, .maxrows, .mode, .open, .output, .read, .schema, .tables, .timer", line.trim())
+        };
+        let (name, arguments) = words.split_first().ok_or_else(invalid)?;
+        let argument = arguments.first().cloned();
+        let command =
+            match (name.as_str(), arguments.len()) {
+                ("tables", 0 | 1) => Self::Tables(argument),
+                ("schema", 0 | 1) => Self::Schema(argument),
+                ("databases", 0) => Self::Databases,
+                ("read", 1) => Self::Read(PathBuf::from(&arguments[0])),
+                ("mode", 0) => Self::Mode(None),
+                ("mode", 1) => Self::Mode(Some(
+                    arguments[0].parse().map_err(|e| format!(".mode: {e}"))?,
+                )),
+                ("timer", 1) => match arguments[0].to_ascii_lowercase().as_str() {
+                    "on" | "1" | "yes" | "true" => Self::Timer(true),
+                    "off" | "0" | "no" | "false" => Self::Timer(false),
+                    _ => {
+                        return Err(format!(
+                            ".timer: expected on or off, got {:?}",
+                            arguments[0]
+                        ))
+                    }
+                },
+                ("output", 0 | 1) => Self::Output(argument.map(PathBuf::from)),
+                ("open", 0 | 1) => Self::Open(argument.map(PathBuf::from)),
+                ("maxrows", 0) => Self::MaxRows(None),
+                ("maxrows", 1) => Self::MaxRows(Some(arguments[0].parse().map_err(|_| {
+                    format!(".maxrows: expected a number, got {:?}", arguments[0])
+                })?)),
+                _ => return Err(invalid()),
+            };
+        Ok(command)
+    }
+
+    /// Query over the catalog that answers `.tables`, `.schema` or `.databases`.
+    pub fn query(&self) -> Option<String> {
+        let pattern = |pattern: &Option<String>| {
+            format!(
+                "'{}'",
+                pattern.as_deref().unwrap_or("%").replace('\'', "''")
+            )
+        };
+        match self {
+            Self::Tables(name) => Some(format!(
+                "SELECT name FROM (\
+                 \n    SELECT table_name AS name FROM duckdb_tables() WHERE NOT internal\
+                 \n    UNION SELECT view_name FROM duckdb_views() WHERE NOT internal\
+                 \n) WHERE name LIKE {} ORDER BY name",
+                pattern(name)
+            )),
+            Self::Schema(name) => Some(format!(
+                "SELECT sql FROM (\
+                 \n    SELECT table_name AS name, 0 AS kind, sql FROM duckdb_tables() WHERE NOT internal\
+                 \n    UNION ALL SELECT view_name, 1, sql FROM duckdb_views() WHERE NOT internal\
+                 \n    UNION ALL SELECT table_name, 2, sql FROM duckdb_indexes() WHERE sql IS NOT NULL\
+                 \n) WHERE name LIKE {} ORDER BY name, kind",
+                pattern(name)
+            )),
+            Self::Databases => Some(
+                "SELECT database_name AS name, coalesce(path, '') AS file, readonly \
+                 FROM duckdb_databases() WHERE NOT internal ORDER BY database_oid"
+                    .to_string(),
+            ),
+            _ => None,
+        }
+    }
+}
+
+/// Part of a script: SQL, or the line of a dot-command.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Piece {
+    Sql(String),
+    Command(String),
+}
+
+/// Whether `line` is a dot-command, when no SQL statement is unfinished.
+fn is_command(line: &str) -> bool {
+    line.trim_start()
+        .strip_prefix('.')
+        .is_some_and(|rest| rest.starts_with(|c: char| c.is_ascii_alphabetic()))
+}
+
+/// Whether `sql` ends in the middle of a statement, which a `.` line continues.
+fn is_unfinished(sql: &str) -> bool {
+    let code: Vec<&str> = sql
+        .lines()
+        .map(|line| line.split_once("--").map_or(line, |(code, _)| code).trim())
+        .filter(|line| !line.is_empty())
+        .collect();
+    code.last().is_some_and(|line| !line.ends_with(';'))
+}
+
+/// Splits a script into SQL and dot-commands, in order, each with the byte
+/// offset where it starts in `script`.
+///
+/// As in the DuckDB CLI, a line starting with `.` is a dot-command unless it
+/// continues a statement. Blank SQL between commands is dropped, but a script
+/// without commands is returned whole.
+pub fn split(script: &str) -> Vec<(usize, Piece)> {
+    let mut pieces = Vec::new();
+    let mut sql = String::new();
+    let mut sql_start = 0;
+    let mut offset = 0;
+    for line in script.split_inclusive('\n') {
+        if is_command(line) && !is_unfinished(&sql) {
+            if !sql.trim().is_empty() {
+                pieces.push((sql_start, Piece::Sql(std::mem::take(&mut sql))));
+            }
+            sql.clear();
+            let indent = line.len() - line.trim_start().len();
+            pieces.push((offset + indent, Piece::Command(line.trim().to_string())));
+            sql_start = offset + line.len();
+        } else {
+            sql.push_str(line);
+        }
+        offset += line.len();
+    }
+    if !sql.trim().is_empty() || pieces.is_empty() {
+        pieces.push((sql_start, Piece::Sql(sql)));
+    }
+    pieces
+}
+
+/// Blanks out the dot-command lines of `script`, keeping the positions of the SQL.
+pub fn mask(script: &str) -> String {
+    let mut masked = String::with_capacity(script.len());
+    let mut sql = String::new();
+    for line in script.split_inclusive('\n') {
+        if is_command(line) && !is_unfinished(&sql) {
+            let content = line.trim_end_matches(['\r', '\n']);
+            masked.push_str(&" ".repeat(content.chars().count()));
+            masked.push_str(&line[content.len()..]);
+            sql.clear();
+        } else {
+            masked.push_str(line);
+            sql.push_str(line);
+        }
+    }
+    masked
+}
+
+/// Splits the arguments of a dot-command at spaces outside quotes.
+fn split_arguments(text: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut quote = None;
+    for c in text.chars() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => word.get_or_insert_with(String::new).push(c),
+            None if c == '\'' || c == '"' => {
+                quote = Some(c);
+                word.get_or_insert_with(String::new);
+            }
+            None if c.is_whitespace() => words.extend(word.take()),
+            None => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    words.extend(word);
+    words
+}
@@ -0,0 +1,306 @@
+use tui_textarea::{CursorMove, TextArea};
+
+/// Keywords after which a new line is indented one level deeper.
+const INDENTING_KEYWORDS: &[&str] = &[
+    "BY", "CASE", "FROM", "HAVING", "QUALIFY", "SELECT", "SET", "VALUES", "WHERE", "WINDOW", "WITH",
+];
+
+/// Returns the selected text, if there is a selection.
+pub fn selected_text(textarea: &TextArea) -> Option<String> {
+    let ((start_row, start_col), (end_row, end_col)) = textarea.selection_range()?;
+    let lines = textarea.lines();
+    let text = if start_row == end_row {
+        lines[start_row]
+            .chars()
+            .skip(start_col)
+            .take(end_col - start_col)
+            .collect()
+    } else {
+        let mut text: String = lines[start_row].chars().skip(start_col).collect();
+        for line in &lines[start_row + 1..end_row] {
+            text.push('\n');
+            text.push_str(line);
+        }
+        text.push('\n');
+        text.extend(lines[end_row].chars().take(end_col));
+        text
+    };
+    Some(text)
+}
+
+/// Selects from `start` to `end`.
+fn select(textarea: &mut TextArea, start: (usize, usize), end: (usize, usize)) {
+    textarea.cancel_selection();
+    textarea.move_cursor(CursorMove::Jump(start.0 as u16, start.1 as u16));
+    textarea.start_selection();
+    textarea.move_cursor(CursorMove::Jump(end.0 as u16, end.1 as u16));
+}
+
+/// Position after `text` when it is inserted at `start`.
+fn end_of(start: (usize, usize), text: &str) -> (usize, usize) {
+    let lines: Vec<&str> = text.split('\n').collect();
+    let last = lines.last().map_or(0, |line| line.chars().count());
+    if lines.len() == 1 {
+        (start.0, start.1 + last)
+    } else {
+        (start.0 + lines.len() - 1, last)
+    }
+}
+
+/// Comments out the selected lines, or the cursor line, with `--`, or
+/// uncomments them if they all are comments.
+pub fn toggle_comment(textarea: &mut TextArea) -> bool {
+    let cursor = textarea.cursor();
+    let selection = textarea.selection_range();
+    let (first, last) = match selection {
+        // A selection ending at the start of a line does not include that line.
+        Some(((start, _), (end, 0))) if end > start => (start, end - 1),
+        Some(((start, _), (end, _))) => (start, end),
+        None => (cursor.0, cursor.0),
+    };
+    let lines = &textarea.lines()[first..=last];
+    let code: Vec<&String> = lines
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .collect();
+    if code.is_empty() {
+        return false;
+    }
+    let indent = |line: &str| line.len() - line.trim_start().len();
+    let commented = code.iter().all(|line| line.trim_start().starts_with("--"));
+    let column = code.iter().map(|line| indent(line)).min().unwrap_or(0);
+
+    let mut cursor_shift = 0isize;
+    let toggled: Vec<String> = lines
+        .iter()
+        .enumerate()
+        .map(|(i, line)| {
+            if line.trim().is_empty() {
+                return line.clone();
+            }
+            let mut line = line.clone();
+            let shift = if commented {
+                let at = indent(&line);
+                let length = if line[at..].starts_with("-- ") { 3 } else { 2 };
+                line.replace_range(at..at + length, "");
+                -(length as isize)
+            } else {
+                line.insert_str(column, "-- ");
+                3
+            };
+            if first + i == cursor.0 {
+                cursor_shift = shift;
+            }
+            line
+        })
+        .collect();
+
+    let end_col = textarea.lines()[last].chars().count();
+    select(textarea, (first, 0), (last, end_col));
+    textarea.insert_str(toggled.join("\n"));
+    if selection.is_some() {
+        let end_col = textarea.lines()[last].chars().count();
+        select(textarea, (first, 0), (last, end_col));
+    } else {
+        let col = cursor.1.saturating_add_signed(cursor_shift);
+        textarea.move_cursor(CursorMove::Jump(cursor.0 as u16, col as u16));
+    }
+    true
+}
+
+/// Wraps the selection, or the code on the cursor line, in `/* */`, or
+/// unwraps it if it already is a block comment.
+pub fn toggle_block_comment(textarea: &mut TextArea) -> bool {
+    let selected = textarea.is_selecting();
+    let (start, text) = match (textarea.selection_range(), selected_text(textarea)) {
+        (Some((start, _)), Some(text)) => (start, text),
+        _ => {
+            let row = textarea.cursor().0;
+            let line = &textarea.lines()[row];
+            if line.trim().is_empty() {
+                return false;
+            }
+            let start = (row, line.chars().take_while(|c| c.is_whitespace()).count());
+            let text = line.trim().to_string();
+            select(textarea, start, end_of(start, &text));
+            (start, text)
+        }
+    };
+    let toggled = match text
+        .strip_prefix("/*")
+        .and_then(|text| text.strip_suffix("*/"))
+    {
+        Some(inner) => {
+            let inner = inner.strip_prefix(' ').unwrap_or(inner);
+            inner.strip_suffix(' ').unwrap_or(inner).to_string()
+        }
+        None => format!("/* {text} */"),
+    };
+    textarea.insert_str(&toggled);
+    select(textarea, start, end_of(start, &toggled));
+    if !selected {
+        textarea.cancel_selection();
+    }
+    true
+}
+
+/// Breaks the line at the cursor, keeping its indentation.
+///
+/// After `(` or a clause keyword the new line is indented by `width` more
+/// spaces, and between `(` and `)` the `)` goes on a line of its own.
+pub fn newline_and_indent(textarea: &mut TextArea, width: usize) -> bool {
+    let (row, col) = textarea.cursor();
+    let line = &textarea.lines()[row];
+    let indent: String = line
+        .chars()
+        .take_while(|c| *c == ' ' || *c == '\t')
+        .collect();
+    let before: String = line.chars().take(col).collect();
+    let before = before.trim_end();
+    let next = line.chars().nth(col);
+    let last_word = before
+        .rsplit(|c: char| !c.is_alphanumeric() && c != '_')
+        .next()
+        .unwrap_or_default()
+        .to_ascii_uppercase();
+    let deeper = format!("{indent}{}", " ".repeat(width));
+
+    if before.ends_with('(') && next == Some(')') {
+        textarea.insert_str(format!("\n{deeper}\n{indent}"));
+        let col = deeper.chars().count();
+        textarea.move_cursor(CursorMove::Jump((row + 1) as u16, col as u16));
+    } else if before.ends_with('(') || INDENTING_KEYWORDS.contains(&last_word.as_str()) {
+        textarea.insert_str(format!("\n{deeper}"));
+    } else {
+        textarea.insert_str(format!("\n{indent}"));
+    }
+    true
+}
+
+/// Types `c`, closing brackets and quotes and typing over closing ones.
+///
+/// Returns false when `c` should be typed as usual.
+pub fn auto_pair(textarea: &mut TextArea, c: char) -> bool {
+    let close = match c {
+        '(' => ')',
+        '[' => ']',
+        '{' => '}',
+        '\'' | '"' => c,
+        ')' | ']' | '}' => {
+            let (row, col) = textarea.cursor();
+            if textarea.lines()[row].chars().nth(col) != Some(c) {
+                return false;
+            }
+            textarea.move_cursor(CursorMove::Forward);
+            return true;
+        }
+        _ => return false,
+    };
+    if let Some(text) = selected_text(textarea) {
+        let (start, _) = textarea.selection_range().unwrap_or_default();
+        textarea.insert_str(format!("{c}{text}{close}"));
+        let inner = (start.0, start.1 + 1);
+        select(textarea, inner, end_of(inner, &text));
+        return true;
+    }
+
+    let (row, col) = textarea.cursor();
+    let line = &textarea.lines()[row];
+    let next = line.chars().nth(col);
+    let prev = col.checked_sub(1).and_then(|i| line.chars().nth(i));
+    if (c == '\'' || c == '"') && next == Some(c) {
+        textarea.move_cursor(CursorMove::Forward);
+        return true;
+    }
+    // Only pair before the end of a word or line, and not for apostrophes within words.
+    let closes = next.is_none_or(|n| n.is_whitespace() || ")]},;".contains(n));
+    let apostrophe = (c == '\'' || c == '"') && prev.is_some_and(|p| p.is_alphanumeric());
+    if !closes || apostrophe {
+        return false;
+    }
+    textarea.insert_str(format!("{c}{close}"));
+    textarea.move_cursor(CursorMove::Back);
+    true
+}
+
+/// Finds the bracket at or just before `cursor` and the one matching it.
+///
+/// Brackets in quotes and comments are ignored.
+pub fn matching_bracket(
+    lines: &[String],
+    cursor: (usize, usize),
+) -> Option<((usize, usize), (usize, usize))> {
+    let mut brackets = Vec::new();
+    let mut quote: Option<char> = None;
+    let mut block_comment = false;
+    for (row, line) in lines.iter().enumerate() {
+        let chars: Vec<char> = line.chars().collect();
+        let mut col = 0;
+        while col < chars.len() {
+            let c = chars[col];
+            let next = chars.get(col + 1).copied();
+            if block_comment {
+                if c == '*' && next == Some('/') {
+                    block_comment = false;
+                    col += 1;
+                }
+            } else if let Some(q) = quote {
+                if c == q {
+                    quote = None;
+                }
+            } else if c == '-' && next == Some('-') {
+                break;
+            } else if c == '/' && next == Some('*') {
+                block_comment = true;
+                col += 1;
+            } else if c == '\'' || c == '"' {
+                quote = Some(c);
+            } else if "()[]{}".contains(c) {
+                brackets.push((c, (row, col)));
+            }
+            col += 1;
+        }
+    }
+
+    let (row, col) = cursor;
+    let at = brackets
+        .iter()
+        .position(|(_, position)| *position == (row, col))
+        .or_else(|| {
+            let before = (row, col.checked_sub(1)?);
+            brackets
+                .iter()
+                .position(|(_, position)| *position == before)
+        })?;
+    let (bracket, position) = brackets[at];
+    let (open, close, forward) = match bracket {
+        '(' => ('(', ')', true),
+        '[' => ('[', ']', true),
+        '{' => ('{', '}', true),
+        ')' => ('(', ')', false),
+        ']' => ('[', ']', false),
+        _ => ('{', '}', false),
+    };
+    let mut depth = 0usize;
+    let candidates: Box<dyn Iterator<Item = &(char, (usize, usize))>> = if forward {
+        Box::new(brackets[at + 1..].iter())
+    } else {
+        Box::new(brackets[..at].iter().rev())
+    };
+    for (c, other) in candidates {
+        let (same, matching) = if forward {
+            (open, close)
+        } else {
+            (close, open)
+        };
+        if *c == same {
+            depth += 1;
+        } else if *c == matching {
+            if depth == 0 {
+                return Some((position, *other));
+            }
+            depth -= 1;
+        }
+    }
+    None
+}
@@ -0,0 +1,759 @@
+use std::collections::HashMap;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use tui_textarea::{CursorMove, TextArea};
+
+/// What the application should do after the editor handled a key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EditorAction {
+    None,
+    /// The buffer text changed.
+    Edited,
+    Save,
+    /// Quit, discarding unsaved buffers when `true`.
+    Quit(bool),
+    SaveQuit,
+    /// Open the file selected in the explorer.
+    Open,
+    /// Open the find bar.
+    Find,
+    /// Report a key sequence or command the editor does not know.
+    Unknown(String),
+}
+
+/// Keymap of the query editor.
+#[derive(Debug, Clone, Default)]
+pub enum EditorMode {
+    /// The default `TextArea` keymap.
+    #[default]
+    Standard,
+    Vim(Vim),
+    Emacs(Emacs),
+}
+
+impl EditorMode {
+    /// Switches to the next keymap.
+    pub fn next(&self) -> Self {
+        match self {
+            EditorMode::Standard => EditorMode::Vim(Vim::default()),
+            EditorMode::Vim(_) => EditorMode::Emacs(Emacs::default()),
+            EditorMode::Emacs(_) => EditorMode::Standard,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            EditorMode::Standard => "standard",
+            EditorMode::Vim(_) => "vim",
+            EditorMode::Emacs(_) => "emacs",
+        }
+    }
+
+    pub fn is_vim(&self) -> bool {
+        matches!(self, EditorMode::Vim(_))
+    }
+
+    pub fn is_emacs(&self) -> bool {
+        matches!(self, EditorMode::Emacs(_))
+    }
+
+    /// Whether keys type text, rather than being commands.
+    pub fn is_inserting(&self) -> bool {
+        match self {
+            EditorMode::Vim(vim) => vim.mode == VimMode::Insert && vim.command.is_none(),
+            _ => true,
+        }
+    }
+
+    /// Whether the next key completes a key sequence, such as `C-x` or `d` in vim.
+    pub fn is_pending(&self) -> bool {
+        match self {
+            EditorMode::Standard => false,
+            EditorMode::Vim(vim) => {
+                vim.command.is_some()
+                    || vim.operator.is_some()
+                    || vim.prefix.is_some()
+                    || vim.count.is_some()
+            }
+            EditorMode::Emacs(emacs) => emacs.prefix,
+        }
+    }
+
+    /// Mode indicator shown in the status line.
+    pub fn status(&self) -> String {
+        match self {
+            EditorMode::Standard => String::new(),
+            EditorMode::Vim(vim) => vim.status(),
+            EditorMode::Emacs(emacs) if emacs.prefix => "EMACS C-x-".to_string(),
+            EditorMode::Emacs(_) => "EMACS".to_string(),
+        }
+    }
+
+    pub fn input(&mut self, key: KeyEvent, textarea: &mut TextArea<'static>) -> EditorAction {
+        match self {
+            EditorMode::Standard => edited(textarea.input(key)),
+            EditorMode::Vim(vim) => vim.input(key, textarea),
+            EditorMode::Emacs(emacs) => emacs.input(key, textarea),
+        }
+    }
+
+    /// Inserts pasted text as a single edit.
+    pub fn paste(&mut self, text: &str, textarea: &mut TextArea<'static>) -> EditorAction {
+        match self {
+            EditorMode::Standard => {}
+            EditorMode::Vim(vim) => return vim.paste(text, textarea),
+            // Yanking does not replace the region in emacs.
+            EditorMode::Emacs(emacs) => {
+                emacs.prefix = false;
+                textarea.cancel_selection();
+            }
+        }
+        edited(textarea.insert_str(text))
+    }
+}
+
+fn edited(modified: bool) -> EditorAction {
+    if modified {
+        EditorAction::Edited
+    } else {
+        EditorAction::None
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum VimMode {
+    #[default]
+    Normal,
+    Insert,
+    Visual,
+    VisualLine,
+}
+
+/// Contents of a vim register.
+#[derive(Debug, Clone, Default)]
+struct Register {
+    text: String,
+    /// Whether the text holds whole lines, which are pasted above or below the cursor line.
+    linewise: bool,
+}
+
+/// Vim keymap with normal, insert and visual modes.
+#[derive(Debug, Clone, Default)]
+pub struct Vim {
+    pub mode: VimMode,
+    /// Operator waiting for a motion, such as `d` in `dw`.
+    operator: Option<char>,
+    /// First key of a two-key command, such as `g` in `gg` or `"` before a register name.
+    prefix: Option<char>,
+    count: Option<usize>,
+    /// Register named with `"` for the next operator.
+    register: Option<char>,
+    registers: HashMap<char, Register>,
+    /// Row where a linewise visual selection started.
+    anchor: usize,
+    /// Text of the `:` command line while it is open.
+    command: Option<String>,
+}
+
+impl Vim {
+    fn status(&self) -> String {
+        if let Some(command) = &self.command {
+            return format!(":{command}");
+        }
+        let mode = match self.mode {
+            VimMode::Normal => "NORMAL",
+            VimMode::Insert => "INSERT",
+            VimMode::Visual => "VISUAL",
+            VimMode::VisualLine => "VISUAL LINE",
+        };
+        let pending: String = self
+            .count
+            .map(|n| n.to_string())
+            .into_iter()
+            .chain(self.operator.map(String::from))
+            .chain(self.prefix.map(String::from))
+            .collect();
+        if pending.is_empty() {
+            mode.to_string()
+        } else {
+            format!("{mode} {pending}")
+        }
+    }
+
+    fn input(&mut self, key: KeyEvent, textarea: &mut TextArea<'static>) -> EditorAction {
+        if self.command.is_some() {
+            return self.command_input(key, textarea);
+        }
+        if self.mode == VimMode::Insert {
+            if key.code == KeyCode::Esc {
+                self.mode = VimMode::Normal;
+                textarea.move_cursor(CursorMove::Back);
+                return EditorAction::None;
+            }
+            return edited(textarea.input(key));
+        }
+
+        let c = match (key.code, key.modifiers) {
+            (KeyCode::Esc, _) => {
+                self.reset();
+                if self.mode != VimMode::Normal {
+                    textarea.cancel_selection();
+                    self.mode = VimMode::Normal;
+                }
+                return EditorAction::None;
+            }
+            (KeyCode::Char('r'), KeyModifiers::CONTROL) => return edited(textarea.redo()),
+            (KeyCode::Char(c), KeyModifiers::NONE | KeyModifiers::SHIFT) => c,
+            (KeyCode::Left, _) => 'h',
+            (KeyCode::Right, _) => 'l',
+            (KeyCode::Up, _) => 'k',
+            (KeyCode::Down, _) => 'j',
+            (KeyCode::Home, _) => '0',
+            (KeyCode::End, _) => '$',
+            (KeyCode::Backspace, _) => 'h',
+            (KeyCode::Enter, _) => '+',
+            _ => return EditorAction::None,
+        };
+        self.normal(c, textarea)
+    }
+
+    fn reset(&mut self) {
+        self.operator = None;
+        self.prefix = None;
+        self.count = None;
+        self.register = None;
+    }
+
+    fn normal(&mut self, c: char, textarea: &mut TextArea<'static>) -> EditorAction {
+        match self.prefix.take() {
+            Some('"') => {
+                self.register = Some(c);
+                return EditorAction::None;
+            }
+            Some('r') => {
+                // There is no character to replace at the end of the line.
+                let (row, col) = textarea.cursor();
+                if col >= textarea.lines()[row].chars().count() {
+                    return EditorAction::None;
+                }
+                textarea.delete_next_char();
+                textarea.insert_char(c);
+                textarea.move_cursor(CursorMove::Back);
+                return EditorAction::Edited;
+            }
+            Some('g') if c == 'g' => return self.motion(CursorMove::Top, true, textarea),
+            Some(prefix) => {
+                self.reset();
+                return EditorAction::Unknown(format!("{prefix}{c}"));
+            }
+            None => {}
+        }
+
+        if c.is_ascii_digit() && (c != '0' || self.count.is_some()) {
+            let digit = c.to_digit(10).unwrap_or_default() as usize;
+            self.count = Some(self.count.unwrap_or(0) * 10 + digit);
+            return EditorAction::None;
+        }
+
+        let motion = match c {
+            'h' => Some((CursorMove::Back, false)),
+            'l' | ' ' => Some((CursorMove::Forward, false)),
+            'j' | '+' => Some((CursorMove::Down, true)),
+            'k' | '-' => Some((CursorMove::Up, true)),
+            'w' => Some((CursorMove::WordForward, false)),
+            'b' => Some((CursorMove::WordBack, false)),
+            'e' => Some((CursorMove::WordEnd, false)),
+            '0' | '^' => Some((CursorMove::Head, false)),
+            '$' => Some((CursorMove::End, false)),
+            'G' => Some((CursorMove::Bottom, true)),
+            '{' => Some((CursorMove::ParagraphBack, true)),
+            '}' => Some((CursorMove::ParagraphForward, true)),
+            _ => None,
+        };
+        if let Some((motion, linewise)) = motion {
+            return self.motion(motion, linewise, textarea);
+        }
+
+        if matches!(self.mode, VimMode::Visual | VimMode::VisualLine) {
+            return self.visual(c, textarea);
+        }
+
+        let count = self.count.take().unwrap_or(1);
+        match c {
+            'g' | '"' | 'r' => {
+                self.prefix = Some(c);
+                self.count = (count > 1).then_some(count);
+                EditorAction::None
+            }
+            'd' | 'c' | 'y' if self.operator == Some(c) => {
+                self.operator = None;
+                let (row, _) = textarea.cursor();
+                let last = (row + count - 1).min(textarea.lines().len() - 1);
+                self.lines(c, row, last, textarea)
+            }
+            'd' | 'c' | 'y' => {
+                self.operator = Some(c);
+                self.count = (count > 1).then_some(count);
+                EditorAction::None
+            }
+            _ if self.operator.is_some() => {
+                self.reset();
+                EditorAction::None
+            }
+            'i' => self.insert(None, textarea),
+            'a' => self.insert(Some(CursorMove::Forward), textarea),
+            'I' => self.insert(Some(CursorMove::Head), textarea),
+            'A' => self.insert(Some(CursorMove::End), textarea),
+            'o' => {
+                textarea.move_cursor(CursorMove::End);
+                textarea.insert_newline();
+                self.mode = VimMode::Insert;
+                EditorAction::Edited
+            }
+            'O' => {
+                textarea.move_cursor(CursorMove::Head);
+                textarea.insert_newline();
+                textarea.move_cursor(CursorMove::Up);
+                self.mode = VimMode::Insert;
+                EditorAction::Edited
+            }
+            'v' => {
+                textarea.start_selection();
+                self.mode = VimMode::Visual;
+                EditorAction::None
+            }
+            'V' => {
+                self.anchor = textarea.cursor().0;
+                textarea.move_cursor(CursorMove::Head);
+                textarea.start_selection();
+                textarea.move_cursor(CursorMove::End);
+                self.mode = VimMode::VisualLine;
+                EditorAction::None
+            }
+            'x' => {
+                let (row, col) = textarea.cursor();
+                let count = count.min(textarea.lines()[row].chars().count().saturating_sub(col));
+                textarea.start_selection();
+                for _ in 0..count {
+                    textarea.move_cursor(CursorMove::Forward);
+                }
+                self.take(textarea, true, false)
+            }
+            'X' => {
+                let count = count.min(textarea.cursor().1);
+                textarea.start_selection();
+                for _ in 0..count {
+                    textarea.move_cursor(CursorMove::Back);
+                }
+                self.take(textarea, true, false)
+            }
+            'D' | 'C' => {
+                textarea.start_selection();
+                textarea.move_cursor(CursorMove::End);
+                let action = self.take(textarea, true, false);
+                if c == 'C' {
+                    self.mode = VimMode::Insert;
+                }
+                action
+            }
+            'Y' => {
+                let (row, _) = textarea.cursor();
+                self.lines('y', row, row, textarea)
+            }
+            'J' => {
+                textarea.move_cursor(CursorMove::End);
+                if textarea.delete_next_char() {
+                    textarea.insert_char(' ');
+                    EditorAction::Edited
+                } else {
+                    EditorAction::None
+                }
+            }
+            'p' | 'P' => self.put(c == 'p', count, textarea),
+            'u' => edited(textarea.undo()),
+            ':' => {
+                self.command = Some(String::new());
+                EditorAction::None
+            }
+            '/' => EditorAction::Find,
+            _ => EditorAction::Unknown(c.to_string()),
+        }
+    }
+
+    fn insert(
+        &mut self,
+        motion: Option<CursorMove>,
+        textarea: &mut TextArea<'static>,
+    ) -> EditorAction {
+        if let Some(motion) = motion {
+            textarea.move_cursor(motion);
+        }
+        self.mode = VimMode::Insert;
+        EditorAction::None
+    }
+
+    /// Moves the cursor, or applies the pending operator up to where the motion ends.
+    fn motion(
+        &mut self,
+        motion: CursorMove,
+        linewise: bool,
+        textarea: &mut TextArea<'static>,
+    ) -> EditorAction {
+        let count = self.count.take().unwrap_or(1);
+        let repeat = if matches!(motion, CursorMove::Top | CursorMove::Bottom) {
+            1
+        } else {
+            count
+        };
+        let Some(operator) = self.operator.take() else {
+            for _ in 0..repeat {
+                textarea.move_cursor(motion);
+            }
+            if self.mode == VimMode::VisualLine {
+                self.extend_lines(textarea);
+            }
+            return EditorAction::None;
+        };
+
+        let (row, _) = textarea.cursor();
+        if linewise {
+            for _ in 0..repeat {
+                textarea.move_cursor(motion);
+            }
+            let (target, _) = textarea.cursor();
+            return self.lines(operator, row.min(target), row.max(target), textarea);
+        }
+
+        textarea.start_selection();
+        for _ in 0..repeat {
+            textarea.move_cursor(motion);
+        }
+        if matches!(motion, CursorMove::WordEnd) {
+            // `e` includes the last character of the word.
+            textarea.move_cursor(CursorMove::Forward);
+        }
+        self.apply(operator, textarea)
+    }
+
+    /// Keeps a linewise visual selection covering whole lines from the anchor.
+    fn extend_lines(&mut self, textarea: &mut TextArea<'static>) {
+        let (row, _) = textarea.cursor();
+        textarea.cancel_selection();
+        let (start, end) = (self.anchor.min(row), self.anchor.max(row));
+        let end_col = textarea.lines()[end].chars().count();
+        if row < self.anchor {
+            textarea.move_cursor(CursorMove::Jump(end as u16, end_col as u16));
+            textarea.start_selection();
+            textarea.move_cursor(CursorMove::Jump(start as u16, 0));
+        } else {
+            textarea.move_cursor(CursorMove::Jump(start as u16, 0));
+            textarea.start_selection();
+            textarea.move_cursor(CursorMove::Jump(end as u16, end_col as u16));
+        }
+    }
+
+    fn visual(&mut self, c: char, textarea: &mut TextArea<'static>) -> EditorAction {
+        let operator = match c {
+            'd' | 'x' => 'd',
+            'c' | 's' => 'c',
+            'y' => 'y',
+            'o' => return EditorAction::None,
+            'v' | 'V' => {
+                textarea.cancel_selection();
+                self.mode = VimMode::Normal;
+                return EditorAction::None;
+            }
+            ':' => {
+                self.command = Some(String::new());
+                return EditorAction::None;
+            }
+            // The visual selection becomes the scope of the search.
+            '/' => {
+                self.mode = VimMode::Normal;
+                return EditorAction::Find;
+            }
+            _ => return EditorAction::Unknown(c.to_string()),
+        };
+        let linewise = self.mode == VimMode::VisualLine;
+        self.mode = VimMode::Normal;
+        if linewise {
+            let (row, _) = textarea.cursor();
+            textarea.cancel_selection();
+            return self.lines(
+                operator,
+                self.anchor.min(row),
+                self.anchor.max(row),
+                textarea,
+            );
+        }
+        // Visual selections include the character under the cursor.
+        textarea.move_cursor(CursorMove::Forward);
+        self.apply(operator, textarea)
+    }
+
+    /// Applies `operator` to the whole lines `start..=end`.
+    fn lines(
+        &mut self,
+        operator: char,
+        start: usize,
+        end: usize,
+        textarea: &mut TextArea<'static>,
+    ) -> EditorAction {
+        let last = textarea.lines().len() - 1;
+        let end_col = textarea.lines()[end].chars().count();
+        if operator == 'y' {
+            let text = textarea.lines()[start..=end].join("\n") + "\n";
+            self.store(text, true);
+            textarea.move_cursor(CursorMove::Jump(start as u16, 0));
+            return EditorAction::None;
+        }
+
+        // Take the line break after the lines, or before them on the last line.
+        if end < last {
+            textarea.move_cursor(CursorMove::Jump(start as u16, 0));
+            textarea.start_selection();
+            textarea.move_cursor(CursorMove::Jump(end as u16 + 1, 0));
+        } else if start > 0 {
+            let prev_col = textarea.lines()[start - 1].chars().count();
+            textarea.move_cursor(CursorMove::Jump(start as u16 - 1, prev_col as u16));
+            textarea.start_selection();
+            textarea.move_cursor(CursorMove::Jump(end as u16, end_col as u16));
+        } else {
+            textarea.move_cursor(CursorMove::Jump(0, 0));
+            textarea.start_selection();
+            textarea.move_cursor(CursorMove::Jump(end as u16, end_col as u16));
+        }
+        let text = textarea.lines()[start..=end].join("\n") + "\n";
+        textarea.cut();
+        self.store(text, true);
+        if operator == 'c' {
+            if end < last {
+                textarea.insert_newline();
+                textarea.move_cursor(CursorMove::Up);
+            } else if start > 0 {
+                textarea.insert_newline();
+            }
+            self.mode = VimMode::Insert;
+        } else if end >= last && start > 0 {
+            textarea.move_cursor(CursorMove::Head);
+        }
+        EditorAction::Edited
+    }
+
+    /// Applies `operator` to the current selection.
+    fn apply(&mut self, operator: char, textarea: &mut TextArea<'static>) -> EditorAction {
+        let action = self.take(textarea, operator != 'y', false);
+        if operator == 'c' {
+            self.mode = VimMode::Insert;
+        }
+        action
+    }
+
+    /// Cuts or copies the selection into the active register, which is left
+    /// unchanged when nothing is selected.
+    fn take(
+        &mut self,
+        textarea: &mut TextArea<'static>,
+        cut: bool,
+        linewise: bool,
+    ) -> EditorAction {
+        let selection = textarea.selection_range();
+        let Some((start, _)) = selection.filter(|(start, end)| start != end) else {
+            textarea.cancel_selection();
+            self.register = None;
+            return EditorAction::None;
+        };
+        let action = if cut {
+            edited(textarea.cut())
+        } else {
+            textarea.copy();
+            textarea.cancel_selection();
+            textarea.move_cursor(CursorMove::Jump(start.0 as u16, start.1 as u16));
+            EditorAction::None
+        };
+        self.store(textarea.yank_text(), linewise);
+        action
+    }
+
+    fn store(&mut self, text: String, linewise: bool) {
+        let register = Register { text, linewise };
+        if let Some(name) = self.register.take() {
+            self.registers.insert(name, register.clone());
+        }
+        self.registers.insert('"', register);
+    }
+
+    /// Pastes the active register after (`p`) or before (`P`) the cursor.
+    fn put(&mut self, after: bool, count: usize, textarea: &mut TextArea<'static>) -> EditorAction {
+        let name = self.register.take().unwrap_or('"');
+        let Some(register) = self.registers.get(&name).cloned() else {
+            return EditorAction::Unknown(format!("\"{name} is empty"));
+        };
+        let text = register.text.repeat(count);
+        if register.linewise {
+            if after {
+                textarea.move_cursor(CursorMove::End);
+                textarea.insert_newline();
+                textarea.insert_str(text.strip_suffix('\n').unwrap_or(&text));
+            } else {
+                textarea.move_cursor(CursorMove::Head);
+                textarea.insert_str(&text);
+                textarea.move_cursor(CursorMove::Up);
+            }
+            textarea.move_cursor(CursorMove::Head);
+        } else {
+            if after {
+                textarea.move_cursor(CursorMove::Forward);
+            }
+            textarea.insert_str(&text);
+        }
+        EditorAction::Edited
+    }
+
+    /// Inserts pasted text at the cursor, or on the command line while it is open.
+    ///
+    /// A visual selection is replaced by the text.
+    fn paste(&mut self, text: &str, textarea: &mut TextArea<'static>) -> EditorAction {
+        if let Some(command) = &mut self.command {
+            command.push_str(text.lines().next().unwrap_or_default());
+            return EditorAction::None;
+        }
+        self.operator = None;
+        self.prefix = None;
+        self.count = None;
+        if matches!(self.mode, VimMode::Visual | VimMode::VisualLine) {
+            self.mode = VimMode::Normal;
+        }
+        edited(textarea.insert_str(text))
+    }
+
+    fn command_input(&mut self, key: KeyEvent, textarea: &mut TextArea<'static>) -> EditorAction {
+        let Some(command) = &mut self.command else {
+            return EditorAction::None;
+        };
+        match key.code {
+            KeyCode::Esc => self.command = None,
+            KeyCode::Backspace if command.is_empty() => self.command = None,
+            KeyCode::Backspace => {
+                command.pop();
+            }
+            KeyCode::Char(c) => command.push(c),
+            KeyCode::Enter => {
+                let command = self.command.take().unwrap_or_default();
+                if self.mode != VimMode::Normal {
+                    textarea.cancel_selection();
+                    self.mode = VimMode::Normal;
+                }
+                return match command.trim() {
+                    "w" => EditorAction::Save,
+                    "q" => EditorAction::Quit(false),
+                    "q!" | "qa!" => EditorAction::Quit(true),
+                    "wq" | "x" => EditorAction::SaveQuit,
+                    line if line.parse::<u16>().is_ok() => {
+                        let line = line.parse::<u16>().unwrap_or(1).saturating_sub(1);
+                        textarea.move_cursor(CursorMove::Jump(line, 0));
+                        EditorAction::None
+                    }
+                    other => EditorAction::Unknown(format!(":{other}")),
+                };
+            }
+            _ => {}
+        }
+        EditorAction::None
+    }
+}
+
+/// Emacs keymap with the mark, the kill ring and `C-x` commands.
+///
+/// Keys without an emacs binding of their own fall back to the `TextArea` keymap,
+/// which already follows emacs for most motions and kills.
+#[derive(Debug, Clone, Default)]
+pub struct Emacs {
+    /// Whether `C-x` was pressed.
+    prefix: bool,
+}
+
+impl Emacs {
+    fn input(&mut self, key: KeyEvent, textarea: &mut TextArea<'static>) -> EditorAction {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let alt = key.modifiers.contains(KeyModifiers::ALT);
+
+        if std::mem::take(&mut self.prefix) {
+            return match (key.code, ctrl) {
+                (KeyCode::Char('s'), true) => EditorAction::Save,
+                (KeyCode::Char('c'), true) => EditorAction::Quit(false),
+                (KeyCode::Char('f'), true) => EditorAction::Open,
+                (KeyCode::Char('u'), false) => edited(textarea.undo()),
+                (KeyCode::Char('h'), false) => {
+                    textarea.select_all();
+                    EditorAction::None
+                }
+                (KeyCode::Char(c), true) => EditorAction::Unknown(format!("C-x C-{c}")),
+                (KeyCode::Char(c), false) => EditorAction::Unknown(format!("C-x {c}")),
+                _ => EditorAction::None,
+            };
+        }
+
+        // Motions keep the mark, so they extend the region while it is active.
+        let motion = match (key.code, ctrl, alt) {
+            (KeyCode::Char('f'), true, false) | (KeyCode::Right, false, false) => {
+                Some(CursorMove::Forward)
+            }
+            (KeyCode::Char('b'), true, false) | (KeyCode::Left, false, false) => {
+                Some(CursorMove::Back)
+            }
+            (KeyCode::Char('n'), true, false) | (KeyCode::Down, false, false) => {
+                Some(CursorMove::Down)
+            }
+            (KeyCode::Char('p'), true, false) | (KeyCode::Up, false, false) => Some(CursorMove::Up),
+            (KeyCode::Char('a'), true, false) | (KeyCode::Home, false, false) => {
+                Some(CursorMove::Head)
+            }
+            (KeyCode::Char('e'), true, false) | (KeyCode::End, false, false) => {
+                Some(CursorMove::End)
+            }
+            (KeyCode::Char('f'), false, true) => Some(CursorMove::WordForward),
+            (KeyCode::Char('b'), false, true) => Some(CursorMove::WordBack),
+            (KeyCode::Char('<'), false, true) => Some(CursorMove::Top),
+            (KeyCode::Char('>'), false, true) => Some(CursorMove::Bottom),
+            (KeyCode::Char('{'), false, true) => Some(CursorMove::ParagraphBack),
+            (KeyCode::Char('}'), false, true) => Some(CursorMove::ParagraphForward),
+            _ => None,
+        };
+        if let Some(motion) = motion {
+            textarea.move_cursor(motion);
+            return EditorAction::None;
+        }
+
+        match (key.code, ctrl, alt) {
+            (KeyCode::Char('x'), true, false) => {
+                self.prefix = true;
+                EditorAction::None
+            }
+            (KeyCode::Char(' ') | KeyCode::Char('@'), true, false) => {
+                textarea.cancel_selection();
+                textarea.start_selection();
+                EditorAction::None
+            }
+            (KeyCode::Char('g'), true, false) => {
+                textarea.cancel_selection();
+                EditorAction::None
+            }
+            (KeyCode::Char('w'), true, false) if textarea.is_selecting() => edited(textarea.cut()),
+            (KeyCode::Char('w'), false, true) => {
+                textarea.copy();
+                textarea.cancel_selection();
+                EditorAction::None
+            }
+            (KeyCode::Char('y'), true, false) => edited(textarea.paste()),
+            (KeyCode::Char('/') | KeyCode::Char('_'), true, false) => edited(textarea.undo()),
+            (KeyCode::Char('o'), true, false) => {
+                textarea.insert_newline();
+                textarea.move_cursor(CursorMove::Up);
+                textarea.move_cursor(CursorMove::End);
+                EditorAction::Edited
+            }
+            _ => edited(textarea.input(key)),
+        }
+    }
+}
@@ -7,7 +7,7 @@ use tokio::sync::mpsc;
 use crate::app::AppResult;
 
 /// Terminal events.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Debug)]
 pub enum Event {
     /// Terminal tick.
     Tick,
@@ -17,6 +17,8 @@ pub enum Event {
     Mouse(MouseEvent),
     /// Terminal resize.
     Resize(u16, u16),
+    /// Text pasted into the terminal, delivered at once with bracketed paste.
+    Paste(String),
 }
 
 /// Terminal event handler.
@@ -29,12 +31,14 @@ pub struct EventHandler {
     receiver: mpsc::UnboundedReceiver<Event>,
     /// Event handler thread.
     handler: tokio::task::JoinHandle<()>,
+    /// Tick rate in milliseconds.
+    tick_rate: u64,
 }
 
 impl EventHandler {
     /// Constructs a new instance of [`EventHandler`].
-    pub fn new(tick_rate: u64) -> Self {
-        let tick_rate = Duration::from_millis(tick_rate);
+    pub fn new(tick_rate_ms: u64) -> Self {
+        let tick_rate = Duration::from_millis(tick_rate_ms);
         let (sender, receiver) = mpsc::unbounded_channel();
         let _sender = sender.clone();
         let handler = tokio::spawn(async move {
@@ -67,7 +71,8 @@ impl EventHandler {
                       },
                       CrosstermEvent::FocusGained => {
                       },
-                      CrosstermEvent::Paste(_) => {
+                      CrosstermEvent::Paste(text) => {
+                        _sender.send(Event::Paste(text)).unwrap();
                       },
                     }
                   }
@@ -78,9 +83,20 @@ impl EventHandler {
             sender,
             receiver,
             handler,
+            tick_rate: tick_rate_ms,
         }
     }
 
+    /// Returns the tick rate in milliseconds.
+    pub fn tick_rate(&self) -> u64 {
+        self.tick_rate
+    }
+
+    /// Stops reading terminal events.
+    pub fn stop(&self) {
+        self.handler.abort();
+    }
+
     /// Receive the next event from the handler thread.
     ///
     /// This function will always block the current thread if
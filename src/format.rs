@@ -0,0 +1,1031 @@
+use std::fmt;
+use std::str::FromStr;
+
+/// How the formatter writes SQL keywords.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum KeywordCase {
+    #[default]
+    Upper,
+    Lower,
+    /// Keeps keywords as they are written.
+    Preserve,
+}
+
+impl FromStr for KeywordCase {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "upper" => Ok(Self::Upper),
+            "lower" => Ok(Self::Lower),
+            "preserve" => Ok(Self::Preserve),
+            _ => Err(format!("expected upper, lower or preserve, got {s:?}")),
+        }
+    }
+}
+
+impl fmt::Display for KeywordCase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Upper => "upper",
+            Self::Lower => "lower",
+            Self::Preserve => "preserve",
+        })
+    }
+}
+
+/// Settings of the SQL formatter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormatOptions {
+    pub keyword_case: KeywordCase,
+    /// Spaces per indentation level.
+    pub indent: usize,
+    /// Lines longer than this are broken up where possible.
+    pub width: usize,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        Self {
+            keyword_case: KeywordCase::Upper,
+            indent: 4,
+            width: 80,
+        }
+    }
+}
+
+/// Marks the start of a line comment in formatted text.
+const COMMENT_START: char = '\u{1}';
+/// Marks the end of a line comment: whatever follows has to go on a new line.
+const COMMENT_END: char = '\u{0}';
+/// Stands for a newline inside a string literal or block comment, kept as written.
+const VERBATIM_NEWLINE: char = '\u{2}';
+
+/// Words written in the configured keyword case.
+const KEYWORDS: &[&str] = &[
+    "ALL",
+    "ALTER",
+    "ANALYZE",
+    "AND",
+    "ANTI",
+    "ANY",
+    "AS",
+    "ASC",
+    "ASOF",
+    "ATTACH",
+    "BEGIN",
+    "BETWEEN",
+    "BIGINT",
+    "BLOB",
+    "BOOLEAN",
+    "BY",
+    "CASE",
+    "CAST",
+    "CHECK",
+    "COLUMNS",
+    "COMMIT",
+    "CONFLICT",
+    "CONSTRAINT",
+    "COPY",
+    "CREATE",
+    "CROSS",
+    "CUBE",
+    "DEFAULT",
+    "DELETE",
+    "DESC",
+    "DESCRIBE",
+    "DETACH",
+    "DISTINCT",
+    "DO",
+    "DOUBLE",
+    "DROP",
+    "ELSE",
+    "END",
+    "ESCAPE",
+    "EXCEPT",
+    "EXCLUDE",
+    "EXISTS",
+    "EXPLAIN",
+    "FALSE",
+    "FILTER",
+    "FIRST",
+    "FLOAT",
+    "FOLLOWING",
+    "FOR",
+    "FOREIGN",
+    "FROM",
+    "FULL",
+    "GLOB",
+    "GROUP",
+    "GROUPING",
+    "HAVING",
+    "HUGEINT",
+    "IF",
+    "ILIKE",
+    "IN",
+    "INNER",
+    "INSERT",
+    "INSTALL",
+    "INT",
+    "INTEGER",
+    "INTERSECT",
+    "INTERVAL",
+    "INTO",
+    "IS",
+    "ISNULL",
+    "JOIN",
+    "LAMBDA",
+    "LAST",
+    "LATERAL",
+    "LEFT",
+    "LIKE",
+    "LIMIT",
+    "LOAD",
+    "MACRO",
+    "MAP",
+    "NATURAL",
+    "NOT",
+    "NOTHING",
+    "NOTNULL",
+    "NULL",
+    "NULLS",
+    "OFFSET",
+    "ON",
+    "OR",
+    "ORDER",
+    "OUTER",
+    "OVER",
+    "PARTITION",
+    "PIVOT",
+    "PIVOT_LONGER",
+    "PIVOT_WIDER",
+    "POSITIONAL",
+    "PRAGMA",
+    "PRECEDING",
+    "PREPARE",
+    "PRIMARY",
+    "QUALIFY",
+    "RANGE",
+    "RECURSIVE",
+    "REFERENCES",
+    "REPLACE",
+    "RETURNING",
+    "RIGHT",
+    "ROLLBACK",
+    "ROLLUP",
+    "ROW",
+    "ROWS",
+    "SAMPLE",
+    "SCHEMA",
+    "SELECT",
+    "SEMI",
+    "SEQUENCE",
+    "SET",
+    "SETS",
+    "SIMILAR",
+    "SMALLINT",
+    "STRUCT",
+    "SUMMARIZE",
+    "TABLE",
+    "TEMP",
+    "TEMPORARY",
+    "THEN",
+    "TINYINT",
+    "TO",
+    "TRANSACTION",
+    "TRUE",
+    "TRY_CAST",
+    "UBIGINT",
+    "UINTEGER",
+    "UNBOUNDED",
+    "UNION",
+    "UNIQUE",
+    "UNNEST",
+    "UNPIVOT",
+    "UPDATE",
+    "USING",
+    "UUID",
+    "VALUES",
+    "VARCHAR",
+    "VIEW",
+    "WHEN",
+    "WHERE",
+    "WINDOW",
+    "WITH",
+    "WITHIN",
+];
+
+/// Keywords that are written like functions, without a space before `(`.
+const CALL_KEYWORDS: &[&str] = &[
+    "CAST", "COLUMNS", "GROUPING", "STRUCT", "TRY_CAST", "UNNEST",
+];
+
+/// Keywords that are also function names; as functions they are kept as written.
+const FUNCTION_WORDS: &[&str] = &[
+    "FILTER", "FIRST", "IF", "LAST", "LEFT", "MAP", "RANGE", "REPLACE", "RIGHT", "ROW", "UUID",
+];
+
+/// Words that start a nested query inside parentheses.
+const QUERY_STARTS: &[&str] = &[
+    "DESCRIBE",
+    "FROM",
+    "PIVOT",
+    "SELECT",
+    "SUMMARIZE",
+    "UNPIVOT",
+    "VALUES",
+    "WITH",
+];
+
+/// Words that can come before `JOIN`.
+const JOIN_PREFIXES: &[&str] = &[
+    "ANTI",
+    "ASOF",
+    "CROSS",
+    "FULL",
+    "INNER",
+    "LEFT",
+    "NATURAL",
+    "OUTER",
+    "POSITIONAL",
+    "RIGHT",
+    "SEMI",
+];
+
+/// Operators made of more than one character, longest first.
+const OPERATORS: &[&str] = &[
+    "->>", "!~~", "::", "->", "=>", ":=", "<=", ">=", "<>", "!=", "==", "||", "**", "//", "<<",
+    ">>", "@>", "<@", "&&", "~~", "^@",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    /// String literal or quoted identifier, kept as written.
+    Quoted(String),
+    Number(String),
+    Operator(String),
+    Comma,
+    Semicolon,
+    Open(char),
+    Close(char),
+    /// A `--` comment and whether it follows code on the same line.
+    LineComment(String, bool),
+    BlockComment(String),
+}
+
+impl Token {
+    fn is_word(&self, word: &str) -> bool {
+        matches!(self, Token::Word(w) if w.eq_ignore_ascii_case(word))
+    }
+
+    fn is_keyword(&self) -> bool {
+        match self {
+            Token::Word(w) => is_keyword(w),
+            _ => false,
+        }
+    }
+
+    fn is_comment(&self) -> bool {
+        matches!(self, Token::LineComment(..) | Token::BlockComment(_))
+    }
+}
+
+fn is_keyword(word: &str) -> bool {
+    KEYWORDS.contains(&word.to_ascii_uppercase().as_str())
+}
+
+/// Splits `sql` into tokens; whitespace is dropped.
+fn tokenize(sql: &str) -> Vec<Token> {
+    let chars: Vec<char> = sql.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    let mut line_has_code = false;
+    let take = |from: usize, to: usize| chars[from..to].iter().collect::<String>();
+
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        let start = i;
+        if c == '\n' {
+            line_has_code = false;
+            i += 1;
+            continue;
+        }
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+        let token = if c == '-' && next == Some('-') {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+            Token::LineComment(take(start, i).trim_end().to_string(), line_has_code)
+        } else if c == '/' && next == Some('*') {
+            i += 2;
+            while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                i += 1;
+            }
+            i = (i + 2).min(chars.len());
+            Token::BlockComment(take(start, i))
+        } else if c == '\'' || c == '"' || ((c == 'E' || c == 'e') && next == Some('\'')) {
+            let quote = if c == '"' { '"' } else { '\'' };
+            i += if c == quote { 1 } else { 2 };
+            while i < chars.len() {
+                if chars[i] == quote {
+                    // A doubled quote is an escaped quote.
+                    if chars.get(i + 1) == Some(&quote) {
+                        i += 2;
+                        continue;
+                    }
+                    i += 1;
+                    break;
+                }
+                if quote == '\'' && chars[i] == '\\' && c != quote {
+                    i += 1;
+                }
+                i += 1;
+            }
+            Token::Quoted(take(start, i.min(chars.len())))
+        } else if c == '$' && next == Some('$') {
+            i += 2;
+            while i < chars.len() && !(chars[i] == '$' && chars.get(i + 1) == Some(&'$')) {
+                i += 1;
+            }
+            i = (i + 2).min(chars.len());
+            Token::Quoted(take(start, i))
+        } else if c.is_ascii_digit() || (c == '.' && next.is_some_and(|n| n.is_ascii_digit())) {
+            while i < chars.len() {
+                let d = chars[i];
+                let exponent_sign =
+                    i > start && matches!(d, '+' | '-') && matches!(chars[i - 1], 'e' | 'E');
+                let decimal_point = d == '.' && chars.get(i + 1) != Some(&'.');
+                if d.is_ascii_alphanumeric() || d == '_' || exponent_sign || decimal_point {
+                    i += 1;
+                } else {
+                    break;
+                }
+            }
+            Token::Number(take(start, i))
+        } else if c.is_alphanumeric() || matches!(c, '_' | '$' | '?') {
+            i += 1;
+            while i < chars.len() && (chars[i].is_alphanumeric() || matches!(chars[i], '_' | '$')) {
+                i += 1;
+            }
+            Token::Word(take(start, i))
+        } else if c == ',' {
+            i += 1;
+            Token::Comma
+        } else if c == ';' {
+            i += 1;
+            Token::Semicolon
+        } else if matches!(c, '(' | '[' | '{') {
+            i += 1;
+            Token::Open(c)
+        } else if matches!(c, ')' | ']' | '}') {
+            i += 1;
+            Token::Close(c)
+        } else {
+            let rest = take(i, (i + 3).min(chars.len()));
+            let operator = OPERATORS
+                .iter()
+                .find(|op| rest.starts_with(*op))
+                .map(|op| op.to_string())
+                .unwrap_or_else(|| c.to_string());
+            i += operator.chars().count();
+            Token::Operator(operator)
+        };
+        line_has_code = true;
+        tokens.push(token);
+    }
+    tokens
+}
+
+/// A token, or a bracketed group of nodes.
+#[derive(Debug, Clone)]
+enum Node {
+    Token(Token),
+    Group(char, Vec<Node>, Option<char>),
+}
+
+impl Node {
+    fn token(&self) -> Option<&Token> {
+        match self {
+            Node::Token(token) => Some(token),
+            Node::Group(..) => None,
+        }
+    }
+
+    fn is_word(&self, word: &str) -> bool {
+        self.token().is_some_and(|token| token.is_word(word))
+    }
+
+    /// Whether this is a parenthesized query, laid out over several lines.
+    fn is_subquery(&self) -> bool {
+        match self {
+            Node::Group('(', nodes, _) => nodes
+                .iter()
+                .find(|node| !node.token().is_some_and(Token::is_comment))
+                .is_some_and(|node| QUERY_STARTS.iter().any(|word| node.is_word(word))),
+            _ => false,
+        }
+    }
+}
+
+/// Builds the bracket tree of `tokens`.
+fn parse(tokens: &mut std::iter::Peekable<std::vec::IntoIter<Token>>, nested: bool) -> Vec<Node> {
+    let mut nodes = Vec::new();
+    while let Some(token) = tokens.peek() {
+        match token {
+            Token::Close(_) if nested => break,
+            Token::Open(open) => {
+                let open = *open;
+                tokens.next();
+                let children = parse(tokens, true);
+                let close = match tokens.next() {
+                    Some(Token::Close(close)) => Some(close),
+                    _ => None,
+                };
+                nodes.push(Node::Group(open, children, close));
+            }
+            _ => nodes.push(Node::Token(tokens.next().unwrap())),
+        }
+    }
+    nodes
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ClauseKind {
+    /// Tokens before the first clause keyword, such as `CREATE TABLE t AS`.
+    Leading,
+    /// Comma separated items, one per line when too long.
+    List,
+    /// Conditions, broken before `AND` and `OR` when too long.
+    Condition,
+    Plain,
+}
+
+struct Clause {
+    comments: Vec<Node>,
+    keyword: Vec<Node>,
+    kind: ClauseKind,
+    body: Vec<Node>,
+}
+
+/// Formats `sql` with `options`.
+///
+/// Comments are kept; everything else is re-spaced and laid out one clause per
+/// line, with nested queries indented.
+pub fn format_sql(sql: &str, options: &FormatOptions) -> String {
+    let formatter = Formatter { options };
+    let mut statements: Vec<Vec<Token>> = vec![Vec::new()];
+    let mut terminated = Vec::new();
+    let mut depth = 0usize;
+    for token in tokenize(sql) {
+        match token {
+            Token::Open(_) => depth += 1,
+            Token::Close(_) => depth = depth.saturating_sub(1),
+            Token::Semicolon if depth == 0 => {
+                terminated.push(true);
+                statements.push(Vec::new());
+                continue;
+            }
+            _ => {}
+        }
+        statements.last_mut().unwrap().push(token);
+    }
+
+    let mut out = String::new();
+    for (i, mut tokens) in statements.into_iter().enumerate() {
+        // A comment on the same line as the `;` stays with the statement before it.
+        let mut trailing = Vec::new();
+        while let Some(Token::LineComment(_, true)) = tokens.first() {
+            trailing.push(tokens.remove(0));
+        }
+        for comment in trailing {
+            out.push(' ');
+            out.push_str(&formatter.word(&comment, false));
+        }
+        if tokens.is_empty() {
+            continue;
+        }
+        if !out.is_empty() {
+            out.push_str("\n\n");
+        }
+        let nodes = parse(&mut tokens.into_iter().peekable(), false);
+        out.push_str(&formatter.clauses(&nodes, 0));
+        if terminated.get(i).copied().unwrap_or(false) {
+            out = before_comment(out, ";");
+        }
+    }
+    resolve_comments(&out)
+}
+
+struct Formatter<'a> {
+    options: &'a FormatOptions,
+}
+
+impl Formatter<'_> {
+    fn indent(&self, depth: usize) -> String {
+        " ".repeat(self.options.indent * depth)
+    }
+
+    /// Whether `line` fits on one line.
+    fn fits(&self, line: &str) -> bool {
+        let line = line.trim_end_matches(COMMENT_END);
+        !line.contains(['\n', COMMENT_END, VERBATIM_NEWLINE])
+            && line.chars().count() <= self.options.width
+    }
+
+    /// Text of a single token; `keyword` is set for words used as keywords.
+    fn word(&self, token: &Token, keyword: bool) -> String {
+        match token {
+            Token::Word(w) if keyword => match self.options.keyword_case {
+                KeywordCase::Upper => w.to_ascii_uppercase(),
+                KeywordCase::Lower => w.to_ascii_lowercase(),
+                KeywordCase::Preserve => w.clone(),
+            },
+            Token::Word(s) | Token::Number(s) | Token::Operator(s) => s.clone(),
+            Token::Quoted(s) | Token::BlockComment(s) => {
+                s.replace('\n', &VERBATIM_NEWLINE.to_string())
+            }
+            Token::LineComment(s, _) => format!("{COMMENT_START}{s}{COMMENT_END}"),
+            Token::Comma => ",".to_string(),
+            Token::Semicolon => ";".to_string(),
+            Token::Open(c) | Token::Close(c) => c.to_string(),
+        }
+    }
+
+    /// Lays out `nodes` as a query, one clause per line.
+    fn clauses(&self, nodes: &[Node], depth: usize) -> String {
+        let indent = self.indent(depth);
+        let mut lines = Vec::new();
+        for clause in split_clauses(nodes) {
+            for comment in &clause.comments {
+                lines.push(format!(
+                    "{indent}{}",
+                    self.inline(std::slice::from_ref(comment), depth)
+                ));
+            }
+            let mut keyword_nodes = clause.keyword.clone();
+            let mut body = clause.body.as_slice();
+            // `SELECT DISTINCT ON (...)` and similar stay together.
+            if clause.kind == ClauseKind::List {
+                let modifiers = match body {
+                    [first, on, Node::Group('(', ..), ..]
+                        if first.is_word("DISTINCT") && on.is_word("ON") =>
+                    {
+                        3
+                    }
+                    [first, ..] if first.is_word("DISTINCT") || first.is_word("ALL") => 1,
+                    _ => 0,
+                };
+                keyword_nodes.extend_from_slice(&body[..modifiers]);
+                body = &body[modifiers..];
+            }
+            let keyword = self.inline(&keyword_nodes, depth);
+            if body.is_empty() {
+                lines.push(format!("{indent}{keyword}"));
+                continue;
+            }
+
+            let separator = if keyword.is_empty() { "" } else { " " };
+            let line = format!(
+                "{indent}{keyword}{separator}{}",
+                self.inline_after(&keyword_nodes, body, depth)
+            );
+            if self.fits(&line) {
+                lines.push(line);
+                continue;
+            }
+            match clause.kind {
+                ClauseKind::List => {
+                    let inner = self.indent(depth + 1);
+                    let items = split_items(body);
+                    let mut text = format!("{indent}{keyword}");
+                    for (i, item) in items.iter().enumerate() {
+                        let mut item = self.inline(item, depth + 1);
+                        if i + 1 < items.len() {
+                            item = before_comment(item, ",");
+                        }
+                        text.push_str(&format!("\n{inner}{item}"));
+                    }
+                    lines.push(text);
+                }
+                ClauseKind::Condition => {
+                    let inner = self.indent(depth + 1);
+                    let mut conditions = split_conditions(body).into_iter();
+                    let first = conditions.next().unwrap_or_default();
+                    let mut text = format!(
+                        "{indent}{keyword}{separator}{}",
+                        self.inline_after(&keyword_nodes, first, depth)
+                    );
+                    for condition in conditions {
+                        text.push_str(&format!("\n{inner}{}", self.inline(condition, depth + 1)));
+                    }
+                    lines.push(text);
+                }
+                ClauseKind::Leading | ClauseKind::Plain => lines.push(line),
+            }
+        }
+        lines.join("\n")
+    }
+
+    /// Writes `nodes` on one line, except for nested queries and comments.
+    fn inline(&self, nodes: &[Node], depth: usize) -> String {
+        let mut out = String::new();
+        self.inline_into(&mut out, nodes, depth, None, &mut Spacing::default());
+        out
+    }
+
+    /// Like [`Formatter::inline`], for `nodes` written after `preceding` on the same line.
+    fn inline_after(&self, preceding: &[Node], nodes: &[Node], depth: usize) -> String {
+        let mut spacing = Spacing::default();
+        self.inline_into(&mut String::new(), preceding, depth, None, &mut spacing);
+        let mut out = String::new();
+        self.inline_into(&mut out, nodes, depth, None, &mut spacing);
+        out.trim_start().to_string()
+    }
+
+    fn inline_into(
+        &self,
+        out: &mut String,
+        nodes: &[Node],
+        depth: usize,
+        bracket: Option<char>,
+        spacing: &mut Spacing,
+    ) {
+        for (i, node) in nodes.iter().enumerate() {
+            let first = match node {
+                Node::Token(token) => token.clone(),
+                Node::Group(open, ..) => Token::Open(*open),
+            };
+            if spacing.space_before(&first, bracket) {
+                out.push(' ');
+            }
+            match node {
+                Node::Token(token) => {
+                    let call = matches!(nodes.get(i + 1), Some(Node::Group('(', ..)))
+                        && is_call(token, spacing.prev.as_ref());
+                    // `name` is only a keyword in `UNION BY NAME`.
+                    let keyword = (token.is_keyword() && !(call && is_function_word_token(token)))
+                        || (token.is_word("NAME")
+                            && spacing.prev.as_ref().is_some_and(|prev| prev.is_word("BY")));
+                    out.push_str(&self.word(token, keyword));
+                    spacing.push(token.clone(), call);
+                }
+                Node::Group(open, children, close) if node.is_subquery() => {
+                    out.push(*open);
+                    out.push('\n');
+                    out.push_str(&self.clauses(children, depth + 1));
+                    out.push('\n');
+                    out.push_str(&self.indent(depth));
+                    out.extend(close);
+                    spacing.push(Token::Close(close.unwrap_or(')')), false);
+                }
+                Node::Group(open, children, close) => {
+                    out.push(*open);
+                    spacing.push(Token::Open(*open), false);
+                    self.inline_into(out, children, depth, Some(*open), spacing);
+                    out.extend(close);
+                    spacing.push(Token::Close(close.unwrap_or(*open)), false);
+                }
+            }
+        }
+    }
+}
+
+fn is_function_word(word: &str) -> bool {
+    FUNCTION_WORDS.contains(&word.to_ascii_uppercase().as_str())
+}
+
+fn is_function_word_token(token: &Token) -> bool {
+    matches!(token, Token::Word(w) if is_function_word(w))
+}
+
+/// Whether `token`, followed by `(`, is a function call.
+fn is_call(token: &Token, prev: Option<&Token>) -> bool {
+    let Token::Word(word) = token else {
+        return false;
+    };
+    let upper = word.to_ascii_uppercase();
+    if !is_keyword(word) || CALL_KEYWORDS.contains(&upper.as_str()) {
+        return true;
+    }
+    // `* REPLACE (...)` and `count(*) FILTER (...)` are keywords.
+    is_function_word(word)
+        && !matches!(prev, Some(Token::Operator(op)) if op == "*")
+        && !matches!(prev, Some(Token::Close(_)))
+}
+
+/// Tracks the tokens written so far on a line, to decide where spaces go.
+#[derive(Default)]
+struct Spacing {
+    prev: Option<Token>,
+    before_prev: Option<Token>,
+    /// Whether `prev` is a function name.
+    call: bool,
+    /// Whether `prev` is a unary operator.
+    unary: bool,
+}
+
+impl Spacing {
+    fn push(&mut self, token: Token, call: bool) {
+        self.unary = matches!(&token, Token::Operator(op) if op == "-" || op == "+")
+            && self.prev.as_ref().is_none_or(|prev| {
+                matches!(
+                    prev,
+                    Token::Operator(_) | Token::Open(_) | Token::Comma | Token::Semicolon
+                ) || prev.is_keyword()
+            });
+        self.call = call;
+        self.before_prev = self.prev.replace(token);
+    }
+
+    /// Whether a space goes before `token`, inside `bracket`.
+    fn space_before(&self, token: &Token, bracket: Option<char>) -> bool {
+        let Some(prev) = &self.prev else {
+            return false;
+        };
+        if self.unary {
+            return false;
+        }
+        match (prev, token) {
+            (Token::LineComment(..), _) => true,
+            (_, Token::Comma | Token::Semicolon | Token::Close(_)) => false,
+            (Token::Open(_), _) => false,
+            (Token::Operator(op), _) | (_, Token::Operator(op)) if op == "." || op == "::" => false,
+            (_, Token::Operator(op)) if op == ":" => false,
+            (Token::Operator(op), _) if op == ":" => bracket != Some('['),
+            (Token::Word(_), Token::Open('(')) => {
+                // `CREATE TABLE t (...)` and `INSERT INTO t (...)` read better with a space.
+                let after_name = self.before_prev.as_ref().is_some_and(|token| {
+                    ["TABLE", "INTO", "VIEW", "EXISTS"]
+                        .iter()
+                        .any(|word| token.is_word(word))
+                });
+                after_name || !self.call
+            }
+            (Token::Close(_), Token::Open('(')) => false,
+            (Token::Word(w), Token::Open('[')) => is_keyword(w),
+            (Token::Quoted(_) | Token::Close(_), Token::Open('[')) => false,
+            _ => true,
+        }
+    }
+}
+
+/// Splits a statement into clauses at top-level clause keywords.
+fn split_clauses(nodes: &[Node]) -> Vec<Clause> {
+    let mut clauses = vec![Clause {
+        comments: Vec::new(),
+        keyword: Vec::new(),
+        kind: ClauseKind::Leading,
+        body: Vec::new(),
+    }];
+    let mut i = 0;
+    while i < nodes.len() {
+        let previous = i.checked_sub(1).map(|j| &nodes[j]);
+        if let Some((length, kind)) = clause_start(&nodes[i..], previous) {
+            let last = clauses.last_mut().unwrap();
+            // Comments on their own line before a clause belong to it.
+            let mut comments = Vec::new();
+            while last
+                .body
+                .last()
+                .and_then(Node::token)
+                .is_some_and(|token| matches!(token, Token::LineComment(_, false)))
+            {
+                comments.insert(0, last.body.pop().unwrap());
+            }
+            clauses.push(Clause {
+                comments,
+                keyword: nodes[i..i + length].to_vec(),
+                kind,
+                body: Vec::new(),
+            });
+            i += length;
+            continue;
+        }
+        clauses.last_mut().unwrap().body.push(nodes[i].clone());
+        i += 1;
+    }
+    clauses.retain(|clause| {
+        clause.kind != ClauseKind::Leading || !clause.body.is_empty() || !clause.comments.is_empty()
+    });
+    clauses
+}
+
+/// Returns the length and kind of the clause keyword starting `nodes`, if any.
+fn clause_start(nodes: &[Node], previous: Option<&Node>) -> Option<(usize, ClauseKind)> {
+    let word = |i: usize| match nodes.get(i).and_then(Node::token) {
+        Some(Token::Word(w)) => Some(w.to_ascii_uppercase()),
+        _ => None,
+    };
+    let first = word(0)?;
+    let previous = previous
+        .and_then(Node::token)
+        .and_then(|token| match token {
+            Token::Word(w) => Some(w.to_ascii_uppercase()),
+            _ => None,
+        })
+        .unwrap_or_default();
+    match first.as_str() {
+        "SELECT" | "WITH" | "RETURNING" | "SET" | "VALUES" | "WINDOW" => {
+            let length = if first == "WITH" && word(1).as_deref() == Some("RECURSIVE") {
+                2
+            } else {
+                1
+            };
+            Some((length, ClauseKind::List))
+        }
+        // `a IS DISTINCT FROM b` and `DELETE FROM t` are not clauses of their own.
+        "FROM" if previous == "DISTINCT" || previous == "DELETE" => None,
+        "FROM" | "LIMIT" | "OFFSET" | "PIVOT" | "UNPIVOT" | "UPDATE" | "DELETE" => {
+            Some((1, ClauseKind::Plain))
+        }
+        "INSERT" => {
+            let length = (1..5)
+                .find(|&i| word(i).as_deref() == Some("INTO"))
+                .map_or(1, |i| i + 1);
+            Some((length, ClauseKind::Plain))
+        }
+        "WHERE" | "HAVING" | "QUALIFY" => Some((1, ClauseKind::Condition)),
+        "GROUP" | "ORDER" if word(1).as_deref() == Some("BY") => Some((2, ClauseKind::List)),
+        "UNION" | "INTERSECT" | "EXCEPT" => {
+            let mut length = 1;
+            if matches!(word(length).as_deref(), Some("ALL" | "DISTINCT")) {
+                length += 1;
+            }
+            if word(length).as_deref() == Some("BY") && word(length + 1).as_deref() == Some("NAME")
+            {
+                length += 2;
+            }
+            Some((length, ClauseKind::Plain))
+        }
+        "JOIN" => Some((1, ClauseKind::Condition)),
+        _ if JOIN_PREFIXES.contains(&first.as_str()) => {
+            let mut length = 1;
+            while word(length)
+                .as_deref()
+                .is_some_and(|w| JOIN_PREFIXES.contains(&w))
+            {
+                length += 1;
+            }
+            (word(length).as_deref() == Some("JOIN")).then_some((length + 1, ClauseKind::Condition))
+        }
+        _ => None,
+    }
+}
+
+/// Splits a list at its top-level commas.
+///
+/// A comment on the same line as a comma goes with the item before it.
+fn split_items(nodes: &[Node]) -> Vec<Vec<Node>> {
+    let mut items = vec![Vec::new()];
+    for node in nodes {
+        match node.token() {
+            Some(Token::Comma) => items.push(Vec::new()),
+            Some(Token::LineComment(_, true))
+                if items.len() > 1 && items.last().unwrap().is_empty() =>
+            {
+                let previous = items.len() - 2;
+                items[previous].push(node.clone());
+            }
+            _ => items.last_mut().unwrap().push(node.clone()),
+        }
+    }
+    items.retain(|item| !item.is_empty());
+    items
+}
+
+/// Splits conditions before their top-level `AND` and `OR`.
+fn split_conditions(nodes: &[Node]) -> Vec<&[Node]> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let mut between = false;
+    for (i, node) in nodes.iter().enumerate() {
+        if node.is_word("BETWEEN") {
+            between = true;
+        } else if node.is_word("AND") && between {
+            between = false;
+        } else if (node.is_word("AND") || node.is_word("OR")) && i > start {
+            parts.push(&nodes[start..i]);
+            start = i;
+        }
+    }
+    parts.push(&nodes[start..]);
+    parts
+}
+
+/// Appends `text` to `line`, before a line comment at its end.
+fn before_comment(mut line: String, text: &str) -> String {
+    if line.ends_with(COMMENT_END) {
+        if let Some(start) = line.rfind(COMMENT_START) {
+            let trimmed = line[..start].trim_end().len();
+            line.insert_str(trimmed, text);
+            return line;
+        }
+    }
+    line.push_str(text);
+    line
+}
+
+/// Breaks lines after line comments and removes the comment markers.
+///
+/// Trailing whitespace is trimmed from the lines of the layout, but not from
+/// lines inside string literals and block comments.
+fn resolve_comments(text: &str) -> String {
+    let mut lines = Vec::new();
+    for line in text.lines() {
+        let mut line = line.replace(COMMENT_START, "");
+        while let Some(end) = line.find(COMMENT_END) {
+            let rest = line[end + COMMENT_END.len_utf8()..]
+                .trim_start()
+                .to_string();
+            line.truncate(end);
+            if rest.is_empty() {
+                break;
+            }
+            let indent = line.len() - line.trim_start().len();
+            lines.push(line.trim_end().to_string());
+            line = format!("{}{rest}", &line[..indent]);
+        }
+        lines.push(line.trim_end().to_string());
+    }
+    lines.join("\n").replace(VERBATIM_NEWLINE, "\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Formats `sql` with the default options, checking that formatting the
+    /// result again leaves it unchanged.
+    fn format(sql: &str) -> String {
+        let options = FormatOptions::default();
+        let formatted = format_sql(sql, &options);
+        assert_eq!(format_sql(&formatted, &options), formatted);
+        formatted
+    }
+
+    #[test]
+    fn from_first() {
+        assert_eq!(
+            format("from sales select day, sum(amount) group by day"),
+            "FROM sales\nSELECT day, sum(amount)\nGROUP BY day"
+        );
+        assert_eq!(format("from tbl"), "FROM tbl");
+    }
+
+    #[test]
+    fn pivot() {
+        assert_eq!(
+            format("pivot sales on year using sum(amount) group by region"),
+            "PIVOT sales ON year USING sum(amount)\nGROUP BY region"
+        );
+    }
+
+    #[test]
+    fn qualify() {
+        assert_eq!(
+            format("select * from t qualify row_number() over (partition by a order by b) = 1"),
+            "SELECT *\nFROM t\nQUALIFY row_number() OVER (PARTITION BY a ORDER BY b) = 1"
+        );
+    }
+
+    #[test]
+    fn lambdas() {
+        assert_eq!(
+            format("select list_transform([1, 2, 3], x -> x * 2), list_filter(l, (x, i) -> x > i) from t"),
+            "SELECT list_transform([1, 2, 3], x -> x * 2), list_filter(l, (x, i) -> x > i)\nFROM t"
+        );
+    }
+
+    #[test]
+    fn columns_star() {
+        assert_eq!(
+            format("select columns(*) from t"),
+            "SELECT COLUMNS(*)\nFROM t"
+        );
+        assert_eq!(
+            format("select min(columns(* exclude (id))) from t where columns('a.*') > 0"),
+            "SELECT min(COLUMNS(* EXCLUDE (id)))\nFROM t\nWHERE COLUMNS('a.*') > 0"
+        );
+    }
+
+    #[test]
+    fn comments() {
+        assert_eq!(
+            format("-- leading\nselect a, -- first\n b /* block */ from t -- tail\nwhere x = 1; -- after"),
+            "-- leading\nSELECT\n    a, -- first\n    b /* block */\nFROM t -- tail\nWHERE x = 1; -- after"
+        );
+    }
+
+    #[test]
+    fn multi_line_literals_are_kept() {
+        assert_eq!(
+            format("select 'line one   \n  line two  ' as s, /* a  \n b */ 1"),
+            "SELECT\n    'line one   \n  line two  ' AS s,\n    /* a  \n b */ 1"
+        );
+    }
+}
@@ -0,0 +1,27 @@
+/// Splits `-- key: value` comment lines at the top of an SQL file from the rest.
+///
+/// Keys are lowercased. The front matter ends at the first line that is not
+/// such a comment; a blank line right after it is not part of the body.
+pub fn parse(text: &str) -> (Vec<(String, String)>, &str) {
+    let mut fields = Vec::new();
+    let mut rest = text;
+    while let Some(comment) = rest.strip_prefix("--") {
+        let (line, next) = comment.split_once('\n').unwrap_or((comment, ""));
+        let Some((key, value)) = line.split_once(':') else {
+            break;
+        };
+        let key = key.trim();
+        if key.is_empty() || !key.chars().all(|c| c.is_alphanumeric() || c == '_') {
+            break;
+        }
+        fields.push((key.to_ascii_lowercase(), value.trim().to_string()));
+        rest = next;
+    }
+    if !fields.is_empty() {
+        rest = rest
+            .strip_prefix("\r\n")
+            .or_else(|| rest.strip_prefix('\n'))
+            .unwrap_or(rest);
+    }
+    (fields, rest)
+}
@@ -1,56 +1,316 @@
-use crate::app::{App, AppResult};
+use crate::app::{App, AppResult, Confirm, Job};
+use crate::search::SearchField;
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use ratatui_explorer::Input;
-use std::fs::read_to_string;
 
 pub fn handle_key_events(key_event: KeyEvent, app: &mut App) -> AppResult<()> {
+    let confirm = app.confirm.take();
     match (key_event.code, key_event.modifiers) {
         (KeyCode::Esc, _) => {
             if app.show_error_popup {
                 app.toggle_error_popup();
+            } else if app.show_cell_inspector {
+                app.toggle_cell_inspector();
             } else if app.show_save_popup {
                 app.toggle_save_popup();
-            } else {
-                app.quit();
+            } else if app.show_rename_popup {
+                app.toggle_rename_popup();
+            } else if app.show_diff_popup {
+                app.toggle_diff_popup(false);
+            } else if app.show_search {
+                app.close_search();
+            } else if app.show_snippet_picker {
+                app.toggle_snippet_picker();
+            } else if app.show_library {
+                app.toggle_library();
+            } else if app.show_library_save {
+                app.toggle_library_save();
+            } else if app.show_params_popup {
+                app.close_params_popup();
+            } else if app.preview.is_some() {
+                app.toggle_preview();
+            } else if app.show_help {
+                app.toggle_help();
+            } else if app.snippet.is_some() {
+                // Leaves the snippet, and in vim also insert mode.
+                app.snippet = None;
+            } else if !app.editor_mode.is_vim() {
+                app.request_quit(confirm == Some(Confirm::Quit));
             }
         }
-        (KeyCode::Char('s'), KeyModifiers::CONTROL) if !app.show_save_popup => {
-            app.toggle_save_popup();
+        (KeyCode::Up, _) if app.show_error_popup => app.select_fix(-1),
+        (KeyCode::Down, _) if app.show_error_popup => app.select_fix(1),
+        (KeyCode::PageUp, _) if app.show_error_popup => app.scroll_error(-5),
+        (KeyCode::PageDown, _) if app.show_error_popup => app.scroll_error(5),
+        (KeyCode::Enter, _) if app.show_error_popup => {
+            app.apply_fix();
+            return Ok(());
         }
+        (KeyCode::Char('c'), _) if app.show_error_popup => app.copy_error(),
+        _ if app.show_error_popup => {}
         (KeyCode::Enter, _) if app.show_save_popup => {
-            if let Err(e) = app.save_to_file(&app.save_popup.input) {
-                app.error = Some(format!("Error saving file: {}", e));
-                app.toggle_error_popup();
+            app.confirm_save();
+            return Ok(());
+        }
+        (KeyCode::Tab, _) if app.show_save_popup => app.save_popup.complete(),
+        (KeyCode::Char(c), _) if app.show_save_popup => app.save_popup.push(c),
+        (KeyCode::Backspace, _) if app.show_save_popup => app.save_popup.pop(),
+        (KeyCode::Enter, _) if app.show_search => {
+            match app.search.field {
+                SearchField::Find => app.step_search(true),
+                SearchField::Replace => app.replace_matches(false),
             }
-            app.toggle_save_popup();
+            return Ok(());
         }
-        (KeyCode::Char(c), _) if app.show_save_popup => {
-            app.save_popup.input.push(c);
+        (KeyCode::Down, _) if app.show_search => app.step_search(true),
+        (KeyCode::Up, _) if app.show_search => app.step_search(false),
+        (KeyCode::Tab | KeyCode::BackTab, _) if app.show_search => {
+            app.search.field = match app.search.field {
+                SearchField::Find => SearchField::Replace,
+                SearchField::Replace => SearchField::Find,
+            };
         }
-        (KeyCode::Backspace, _) if app.show_save_popup => {
-            app.save_popup.input.pop();
+        (KeyCode::Char('r'), KeyModifiers::ALT) if app.show_search => {
+            app.search.regex = !app.search.regex;
+            app.update_search();
         }
-        (KeyCode::F(2), _) => {
-            if let Err(e) = app.create_table() {
-                app.results.clear();
-                app.error = Some(format!("Error: {}", e));
-                app.toggle_error_popup();
+        (KeyCode::Char('c'), KeyModifiers::ALT) if app.show_search => {
+            app.search.case_sensitive = !app.search.case_sensitive;
+            app.update_search();
+        }
+        (KeyCode::Char('a'), KeyModifiers::ALT) if app.show_search => app.replace_matches(true),
+        (KeyCode::Char(c), KeyModifiers::NONE | KeyModifiers::SHIFT) if app.show_search => {
+            app.search.input().push(c);
+            if app.search.field == SearchField::Find {
+                app.update_search();
+            }
+        }
+        (KeyCode::Backspace, _) if app.show_search => {
+            app.search.input().pop();
+            if app.search.field == SearchField::Find {
+                app.update_search();
             }
         }
-        (KeyCode::Char('a'), KeyModifiers::CONTROL) => {
+        (KeyCode::Enter, _) if app.show_snippet_picker => {
+            app.pick_snippet();
+            return Ok(());
+        }
+        (KeyCode::Down, _) if app.show_snippet_picker => app.snippet_picker.select(1),
+        (KeyCode::Up, _) if app.show_snippet_picker => app.snippet_picker.select(-1),
+        (KeyCode::Char(c), KeyModifiers::NONE | KeyModifiers::SHIFT) if app.show_snippet_picker => {
+            app.snippet_picker.push(c);
+        }
+        (KeyCode::Backspace, _) if app.show_snippet_picker => app.snippet_picker.pop(),
+        _ if app.show_snippet_picker => return Ok(()),
+        (KeyCode::Enter, KeyModifiers::ALT) if app.show_library => {
+            app.run_saved_query();
+            return Ok(());
+        }
+        (KeyCode::Enter, _) if app.show_library => {
+            app.insert_saved_query();
+            return Ok(());
+        }
+        (KeyCode::Down, _) if app.show_library => app.library_picker.select(1),
+        (KeyCode::Up, _) if app.show_library => app.library_picker.select(-1),
+        (KeyCode::Char(c), KeyModifiers::NONE | KeyModifiers::SHIFT) if app.show_library => {
+            app.library_picker.push(c);
+        }
+        (KeyCode::Backspace, _) if app.show_library => app.library_picker.pop(),
+        _ if app.show_library => return Ok(()),
+        (KeyCode::Enter, _) if app.show_library_save => {
+            app.save_to_library();
+            return Ok(());
+        }
+        (KeyCode::Tab | KeyCode::Down, _) if app.show_library_save => app.library_form.focus(1),
+        (KeyCode::BackTab | KeyCode::Up, _) if app.show_library_save => {
+            app.library_form.focus(-1);
+        }
+        (KeyCode::Char(c), KeyModifiers::NONE | KeyModifiers::SHIFT) if app.show_library_save => {
+            app.library_form.input().push(c);
+        }
+        (KeyCode::Backspace, _) if app.show_library_save => {
+            app.library_form.input().pop();
+        }
+        _ if app.show_library_save => return Ok(()),
+        (KeyCode::Enter, _) if app.show_params_popup => {
+            app.run_with_parameters();
+            return Ok(());
+        }
+        (KeyCode::Tab | KeyCode::Down, _) if app.show_params_popup => app.params_form.focus(1),
+        (KeyCode::BackTab | KeyCode::Up, _) if app.show_params_popup => {
+            app.params_form.focus(-1);
+        }
+        (KeyCode::Char(c), KeyModifiers::NONE | KeyModifiers::SHIFT) if app.show_params_popup => {
+            app.params_form.input().push(c);
+        }
+        (KeyCode::Backspace, _) if app.show_params_popup => {
+            app.params_form.input().pop();
+        }
+        _ if app.show_params_popup => return Ok(()),
+        (KeyCode::F(1), _) => {
+            app.toggle_help();
+            return Ok(());
+        }
+        (KeyCode::Char('v'), KeyModifiers::ALT) => {
+            app.toggle_preview();
+            return Ok(());
+        }
+        (KeyCode::Char('l'), KeyModifiers::ALT) => {
+            app.toggle_library();
+            return Ok(());
+        }
+        (KeyCode::Char('b'), KeyModifiers::ALT) => {
+            app.toggle_library_save();
+            return Ok(());
+        }
+        (KeyCode::Char('n'), KeyModifiers::ALT) => {
+            app.toggle_snippet_picker();
+            return Ok(());
+        }
+        (KeyCode::Char('f'), KeyModifiers::CONTROL) if !app.editor_mode.is_emacs() => {
+            app.open_search();
+            return Ok(());
+        }
+        // Emacs searches with C-s and saves with C-x C-s, which the editor handles.
+        (KeyCode::Char('s'), KeyModifiers::CONTROL)
+            if app.editor_mode.is_emacs() && !app.editor_mode.is_pending() =>
+        {
+            app.open_search();
+            return Ok(());
+        }
+        (KeyCode::Char('s'), KeyModifiers::CONTROL) if !app.editor_mode.is_emacs() => app.save(),
+        (KeyCode::Char('s'), KeyModifiers::ALT) => app.toggle_save_popup(),
+        (KeyCode::Enter, _) if app.show_rename_popup => {
+            app.rename_tab();
+            return Ok(());
+        }
+        (KeyCode::Char(c), _) if app.show_rename_popup => {
+            app.rename_popup.input.push(c);
+        }
+        (KeyCode::Backspace, _) if app.show_rename_popup => {
+            app.rename_popup.input.pop();
+        }
+        (KeyCode::Enter, _) if app.show_diff_popup => {
+            app.compare();
+            return Ok(());
+        }
+        (KeyCode::Char(c), _) if app.show_diff_popup => {
+            app.diff_popup.input.push(c);
+        }
+        (KeyCode::Backspace, _) if app.show_diff_popup => {
+            app.diff_popup.input.pop();
+        }
+        (KeyCode::F(2), _) => app.start_job(Job::Query),
+        (KeyCode::Char('e'), KeyModifiers::ALT) => {
+            app.cycle_editor_mode();
+            return Ok(());
+        }
+        (KeyCode::Char('a'), KeyModifiers::CONTROL) if !app.editor_mode.is_emacs() => {
             if let Some(path) = app.file_explorer.current().path().to_str() {
-                app.textarea.insert_str(path);
+                let path = path.to_string();
+                app.buffer_mut().textarea.insert_str(path);
+                app.buffer_edited();
+            }
+            return Ok(());
+        }
+        (KeyCode::Char('o'), KeyModifiers::CONTROL) if !app.editor_mode.is_emacs() => {
+            app.open_file();
+            return Ok(());
+        }
+        (KeyCode::Char('m'), KeyModifiers::ALT) => {
+            app.set_result_mode(app.result_mode.next());
+            return Ok(());
+        }
+        (KeyCode::Char('i'), KeyModifiers::ALT) => {
+            app.toggle_types();
+            return Ok(());
+        }
+        (KeyCode::Char('q'), KeyModifiers::ALT) => {
+            app.format_query();
+            return Ok(());
+        }
+        (KeyCode::Char('o'), KeyModifiers::ALT) => {
+            app.external_edit = true;
+            return Ok(());
+        }
+        (KeyCode::Char('t'), KeyModifiers::CONTROL) => app.new_buffer(),
+        (KeyCode::Char('q'), KeyModifiers::CONTROL) => {
+            app.close_buffer(confirm == Some(Confirm::CloseBuffer));
+        }
+        (KeyCode::PageUp, KeyModifiers::CONTROL) => {
+            app.cycle_buffer(-1);
+            return Ok(());
+        }
+        (KeyCode::PageDown, KeyModifiers::CONTROL) => {
+            app.cycle_buffer(1);
+            return Ok(());
+        }
+        (KeyCode::F(3), _) => app.toggle_record_view(),
+        (KeyCode::F(4), _) => app.toggle_cell_inspector(),
+        (KeyCode::F(5), _) => app.unnest_selected(),
+        (KeyCode::F(6), KeyModifiers::SHIFT) => app.start_job(Job::ProfileFile),
+        (KeyCode::F(6), _) => app.start_job(Job::Profile),
+        (KeyCode::F(7), _) => app.toggle_chart(),
+        (KeyCode::F(9), _) => app.start_job(Job::Plan),
+        (KeyCode::F(10), _) => app.toggle_log(),
+        (KeyCode::PageUp, KeyModifiers::ALT) if app.show_log => {
+            app.scroll_log(5);
+            return Ok(());
+        }
+        (KeyCode::PageDown, KeyModifiers::ALT) if app.show_log => {
+            app.scroll_log(-5);
+            return Ok(());
+        }
+        (KeyCode::F(8), KeyModifiers::SHIFT) => app.toggle_diff_popup(true),
+        (KeyCode::F(8), _) => app.toggle_diff_popup(false),
+        (KeyCode::Char('k'), KeyModifiers::ALT) if app.chart_data.is_some() => {
+            app.next_chart_kind();
+        }
+        (KeyCode::Char('x'), KeyModifiers::ALT) if app.chart_data.is_some() => app.set_chart_x(),
+        (KeyCode::Char('y'), KeyModifiers::ALT) if app.chart_data.is_some() => {
+            app.toggle_chart_series();
+        }
+        (KeyCode::Enter, KeyModifiers::ALT)
+            if app.show_cell_inspector || app.show_record_view || app.plan.is_some() =>
+        {
+            app.toggle_node();
+            return Ok(());
+        }
+        (KeyCode::Up, KeyModifiers::ALT) => {
+            if app.show_cell_inspector || app.show_record_view || app.plan.is_some() {
+                app.select_node(-1);
+            } else {
+                app.select_row(-1);
+            }
+        }
+        (KeyCode::Down, KeyModifiers::ALT) => {
+            if app.show_cell_inspector || app.show_record_view || app.plan.is_some() {
+                app.select_node(1);
+            } else {
+                app.select_row(1);
+            }
+        }
+        (KeyCode::Left, KeyModifiers::ALT) => {
+            if app.show_record_view {
+                app.select_row(-1);
+            } else {
+                app.select_column(-1);
             }
         }
-        (KeyCode::Char('o'), KeyModifiers::CONTROL) => {
-            if app.file_explorer.current().path().is_file() {
-                app.textarea.select_all();
-                app.textarea.cut();
-                if let Ok(content) = read_to_string(app.file_explorer.current().path()) {
-                    app.textarea.insert_str(&content);
-                }
+        (KeyCode::Right, KeyModifiers::ALT) => {
+            if app.show_record_view {
+                app.select_row(1);
+            } else {
+                app.select_column(1);
             }
         }
+        (KeyCode::Char(c @ '1'..='9'), KeyModifiers::ALT) => {
+            app.select_tab(c as usize - '1' as usize);
+        }
+        (KeyCode::Char('p'), KeyModifiers::ALT) => app.toggle_pin(),
+        (KeyCode::Char('t'), KeyModifiers::ALT) => app.store_temp_table(),
+        (KeyCode::Char('r'), KeyModifiers::ALT) => app.toggle_rename_popup(),
+        (KeyCode::Char('w'), KeyModifiers::ALT) => app.close_tab(),
         (KeyCode::Down, KeyModifiers::SUPER) => app.file_explorer.handle(Input::Down)?,
         (KeyCode::Up, KeyModifiers::SUPER) => app.file_explorer.handle(Input::Up)?,
         (KeyCode::Left, KeyModifiers::SUPER) => app.file_explorer.handle(Input::Left)?,
@@ -58,10 +318,51 @@ pub fn handle_key_events(key_event: KeyEvent, app: &mut App) -> AppResult<()> {
         _ => {}
     }
 
-    if !app.show_save_popup {
-        app.textarea.input(key_event);
-        app.input = app.textarea.lines().join("\n");
+    if !app.show_error_popup
+        && !app.show_save_popup
+        && !app.show_rename_popup
+        && !app.show_diff_popup
+        && !app.show_search
+    {
+        app.edit(key_event);
+        if !app.buffer().textarea.is_selecting() {
+            app.error_underline = false;
+        }
     }
 
     Ok(())
 }
+
+/// Inserts pasted text into the popup or editor that has focus.
+pub fn handle_paste(text: String, app: &mut App) {
+    let text = text.replace("\r\n", "\n").replace('\r', "\n");
+    // Popups take a single line.
+    let first_line = text.lines().next().unwrap_or_default();
+    if app.show_error_popup {
+        return;
+    }
+    if app.show_save_popup {
+        app.save_popup.push_str(first_line);
+    } else if app.show_rename_popup {
+        app.rename_popup.input.push_str(first_line);
+    } else if app.show_snippet_picker {
+        app.snippet_picker.push_str(first_line);
+    } else if app.show_library {
+        app.library_picker.push_str(first_line);
+    } else if app.show_library_save {
+        app.library_form.input().push_str(first_line);
+    } else if app.show_params_popup {
+        app.params_form.input().push_str(first_line);
+    } else if app.show_search {
+        app.search.input().push_str(first_line);
+        if app.search.field == SearchField::Find {
+            app.update_search();
+        }
+    } else if app.show_diff_popup {
+        // A query can span lines, so they are joined.
+        let query = text.lines().map(str::trim).collect::<Vec<_>>().join(" ");
+        app.diff_popup.input.push_str(&query);
+    } else {
+        app.paste(&text);
+    }
+}
@@ -0,0 +1,173 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+/// Editing actions that can be bound to keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    ToggleComment,
+    ToggleBlockComment,
+    /// Breaks the line, keeping its indentation and indenting after `(` and clause keywords.
+    NewlineAndIndent,
+    /// Closes brackets and quotes as they are typed, and types over the closing ones.
+    AutoPair,
+    /// Expands the snippet whose trigger is before the cursor, or moves to the
+    /// next placeholder of the snippet being filled in.
+    ExpandSnippet,
+    PreviousPlaceholder,
+}
+
+impl Action {
+    const ALL: [Action; 6] = [
+        Action::ToggleComment,
+        Action::ToggleBlockComment,
+        Action::NewlineAndIndent,
+        Action::AutoPair,
+        Action::ExpandSnippet,
+        Action::PreviousPlaceholder,
+    ];
+
+    /// Name of the action in the config file.
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::ToggleComment => "toggle_comment",
+            Action::ToggleBlockComment => "toggle_block_comment",
+            Action::NewlineAndIndent => "newline_and_indent",
+            Action::AutoPair => "auto_pair",
+            Action::ExpandSnippet => "expand_snippet",
+            Action::PreviousPlaceholder => "previous_placeholder",
+        }
+    }
+
+    /// Whether the action only applies while inserting, as its keys type text otherwise.
+    pub fn inserts(self) -> bool {
+        !matches!(self, Action::ToggleComment | Action::ToggleBlockComment)
+    }
+
+    fn default_keys(self) -> &'static str {
+        match self {
+            Action::ToggleComment => "alt+;",
+            Action::ToggleBlockComment => "alt+:",
+            Action::NewlineAndIndent => "enter",
+            Action::AutoPair => "( ) [ ] { } ' \"",
+            Action::ExpandSnippet => "tab",
+            Action::PreviousPlaceholder => "backtab",
+        }
+    }
+}
+
+/// A key with its modifiers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyBinding {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl KeyBinding {
+    /// Parses keys such as `enter`, `ctrl+k`, `alt+;` or `(`.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut rest = text;
+        while let Some((modifier, key)) = rest.split_once('+').filter(|(_, key)| !key.is_empty()) {
+            modifiers |= match modifier.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" | "meta" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => return Err(format!("unknown modifier {modifier:?} in {text:?}")),
+            };
+            rest = key;
+        }
+        let code = match rest.to_ascii_lowercase().as_str() {
+            "enter" | "return" => KeyCode::Enter,
+            "tab" => KeyCode::Tab,
+            "backtab" => KeyCode::BackTab,
+            "backspace" => KeyCode::Backspace,
+            "delete" | "del" => KeyCode::Delete,
+            "esc" | "escape" => KeyCode::Esc,
+            "space" => KeyCode::Char(' '),
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            key if key.len() > 1 && key.starts_with('f') => key[1..]
+                .parse()
+                .map(KeyCode::F)
+                .map_err(|_| format!("unknown key {rest:?}"))?,
+            _ => {
+                let mut chars = rest.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => KeyCode::Char(c),
+                    _ => return Err(format!("unknown key {rest:?}")),
+                }
+            }
+        };
+        Ok(Self { code, modifiers })
+    }
+
+    /// Whether `key` is this binding.
+    ///
+    /// Shift is ignored for characters and Shift+Tab, since terminals differ in
+    /// reporting it.
+    pub fn matches(&self, key: &KeyEvent) -> bool {
+        if key.code != self.code {
+            return false;
+        }
+        match key.code {
+            KeyCode::Char(_) | KeyCode::BackTab => {
+                key.modifiers.difference(KeyModifiers::SHIFT)
+                    == self.modifiers.difference(KeyModifiers::SHIFT)
+            }
+            _ => key.modifiers == self.modifiers,
+        }
+    }
+}
+
+/// Keys bound to editing actions.
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    bindings: Vec<(KeyBinding, Action)>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let mut keymap = Self {
+            bindings: Vec::new(),
+        };
+        for action in Action::ALL {
+            keymap
+                .bind(action.name(), action.default_keys())
+                .expect("default keys are valid");
+        }
+        keymap
+    }
+}
+
+impl Keymap {
+    /// Binds the action called `name` to the whitespace separated `keys`,
+    /// replacing its previous keys. `none` or no keys unbind it.
+    pub fn bind(&mut self, name: &str, keys: &str) -> Result<(), String> {
+        let action = Action::ALL
+            .into_iter()
+            .find(|action| action.name() == name)
+            .ok_or_else(|| format!("unknown action {name}"))?;
+        let bindings = keys
+            .split_whitespace()
+            .filter(|key| *key != "none")
+            .map(KeyBinding::parse)
+            .collect::<Result<Vec<_>, _>>()?;
+        self.bindings.retain(|(_, bound)| *bound != action);
+        self.bindings
+            .extend(bindings.into_iter().map(|binding| (binding, action)));
+        Ok(())
+    }
+
+    /// Returns the action bound to `key`, if any.
+    pub fn action(&self, key: &KeyEvent) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|(binding, _)| binding.matches(key))
+            .map(|(_, action)| *action)
+    }
+}
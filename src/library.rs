@@ -0,0 +1,131 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::front_matter;
+
+/// A named query of the library, stored as a `.sql` file with front matter.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SavedQuery {
+    pub name: String,
+    pub description: String,
+    pub tags: Vec<String>,
+    pub sql: String,
+}
+
+impl SavedQuery {
+    /// Reads a query from the text of its file, named after the file when the
+    /// front matter has no name.
+    pub fn parse(text: &str, file_name: &str) -> Self {
+        let (fields, body) = front_matter::parse(text);
+        let mut query = Self {
+            name: file_name.to_string(),
+            sql: body.trim_end().to_string(),
+            ..Self::default()
+        };
+        for (key, value) in fields {
+            match key.as_str() {
+                "name" if !value.is_empty() => query.name = value,
+                "description" => query.description = value,
+                "tags" => query.tags = parse_tags(&value),
+                _ => {}
+            }
+        }
+        query
+    }
+
+    /// Text of the query's file.
+    pub fn to_file(&self) -> String {
+        let mut text = format!("-- name: {}\n", self.name);
+        if !self.description.is_empty() {
+            text.push_str(&format!("-- description: {}\n", self.description));
+        }
+        if !self.tags.is_empty() {
+            text.push_str(&format!("-- tags: {}\n", self.tags.join(", ")));
+        }
+        text.push('\n');
+        text.push_str(self.sql.trim_end());
+        text.push('\n');
+        text
+    }
+
+    /// Name of the query's file, derived from its name.
+    pub fn file_name(&self) -> String {
+        format!("{}.sql", self.file_stem())
+    }
+
+    /// File name without extension: the name in lowercase, with runs of other
+    /// characters replaced by `_`.
+    fn file_stem(&self) -> String {
+        let mut stem = String::new();
+        for c in self.name.trim().chars() {
+            if c.is_alphanumeric() {
+                stem.extend(c.to_lowercase());
+            } else if !stem.is_empty() && !stem.ends_with('_') {
+                stem.push('_');
+            }
+        }
+        let stem = stem.trim_end_matches('_');
+        if stem.is_empty() { "query" } else { stem }.to_string()
+    }
+}
+
+/// Splits comma or space separated tags, dropping a leading `#`.
+pub fn parse_tags(text: &str) -> Vec<String> {
+    text.split(|c: char| c == ',' || c.is_whitespace())
+        .map(|tag| tag.trim_start_matches('#'))
+        .filter(|tag| !tag.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Loads the queries of the library in `dir`, sorted by name.
+///
+/// Files that can't be read are skipped and returned as warnings.
+pub fn load(dir: &Path) -> (Vec<SavedQuery>, Vec<String>) {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return (Vec::new(), Vec::new()),
+        Err(e) => return (Vec::new(), vec![format!("{}: {e}", dir.display())]),
+    };
+    let mut queries = Vec::new();
+    let mut warnings = Vec::new();
+    for path in entries.filter_map(|entry| Some(entry.ok()?.path())) {
+        if path.extension().is_none_or(|extension| extension != "sql") {
+            continue;
+        }
+        match fs::read_to_string(&path) {
+            Ok(text) => queries.push(SavedQuery::parse(&text, &file_stem(&path))),
+            Err(e) => warnings.push(format!("{}: {e}", path.display())),
+        }
+    }
+    queries.sort_by_key(|query| query.name.to_lowercase());
+    (queries, warnings)
+}
+
+/// Name of the file at `path` without its extension.
+fn file_stem(path: &Path) -> String {
+    path.file_stem()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Writes `query` to its file in `dir`, replacing a query saved under the same name.
+///
+/// When the file belongs to another query whose name gives the same file name,
+/// a numeric suffix is added instead, as in `daily_revenue_2.sql`.
+pub fn save(dir: &Path, query: &SavedQuery) -> io::Result<PathBuf> {
+    fs::create_dir_all(dir)?;
+    let stem = query.file_stem();
+    let mut path = dir.join(query.file_name());
+    for n in 2.. {
+        match fs::read_to_string(&path) {
+            Err(e) if e.kind() == io::ErrorKind::NotFound => break,
+            Ok(text) if SavedQuery::parse(&text, &file_stem(&path)).name == query.name => break,
+            _ => path = dir.join(format!("{stem}_{n}.sql")),
+        }
+    }
+    fs::write(&path, query.to_file())?;
+    Ok(path)
+}
@@ -1,4 +1,4 @@
-use std::io;
+use std::{env, io, process};
 
 use crossterm::event::MouseEventKind;
 use ratatui::{backend::CrosstermBackend, Terminal};
@@ -6,17 +6,42 @@ use ratatui::{backend::CrosstermBackend, Terminal};
 use crate::{
     app::{App, AppResult},
     event::{Event, EventHandler},
-    handler::handle_key_events,
+    handler::{handle_key_events, handle_paste},
     tui::Tui,
 };
 
 pub mod app;
+pub mod buffer;
+pub mod chart;
+pub mod config;
 pub mod custom_table;
+pub mod diagnostic;
+pub mod diff;
+pub mod dot;
+pub mod editing;
+pub mod editor;
 pub mod event;
+pub mod format;
+pub mod front_matter;
 pub mod handler;
+pub mod keymap;
+pub mod library;
+pub mod nested;
+pub mod notify;
+pub mod output;
+pub mod params;
+pub mod plan;
 pub mod popup;
+pub mod profile;
+pub mod results;
+pub mod search;
+pub mod snippet;
+pub mod stats;
+pub mod tree;
 pub mod tui;
 pub mod ui;
+pub mod validate;
+pub mod variables;
 
 // fn execute_query(&mut self) -> DuckResult<()> {
 //     let mut stmt = self.connection.prepare(&self.input)?;
@@ -24,10 +49,36 @@ pub mod ui;
 //     Ok(())
 // }
 
+/// Reads `--var NAME=value` options from the command line.
+fn variables_from_args() -> Result<Vec<(String, String)>, String> {
+    let mut args = env::args().skip(1);
+    let mut variables = Vec::new();
+    while let Some(arg) = args.next() {
+        let assignment = match arg.strip_prefix("--var") {
+            Some("") => args.next().ok_or("--var needs NAME=value")?,
+            Some(rest) if rest.starts_with('=') => rest[1..].to_string(),
+            _ => return Err(format!("unknown argument {arg}")),
+        };
+        match assignment.split_once('=') {
+            Some((name, value)) if variables::is_name(name) => {
+                variables.push((name.to_string(), value.to_string()));
+            }
+            _ => return Err(format!("--var {assignment}: expected NAME=value")),
+        }
+    }
+    Ok(variables)
+}
+
 #[tokio::main]
 async fn main() -> AppResult<()> {
+    let variables = variables_from_args().unwrap_or_else(|e| {
+        eprintln!("quackmire: {e}\nusage: quackmire [--var NAME=value]...");
+        process::exit(2);
+    });
+
     // Create an application.
     let mut app = App::new()?;
+    app.config.variables.extend(variables);
 
     // Initialize the terminal user interface.
     let backend = CrosstermBackend::new(io::stdout());
@@ -45,23 +96,46 @@ async fn main() -> AppResult<()> {
             Event::Tick => app.tick(),
             Event::Key(key_event) => {
                 handle_key_events(key_event, &mut app);
+                if app.job.is_some() {
+                    tui.draw(&mut app)?;
+                    app.run_job();
+                }
+                if app.external_edit {
+                    app.external_edit = false;
+                    tui.suspend()?;
+                    app.edit_externally();
+                    tui.resume()?;
+                }
             }
             Event::Mouse(mouse) => match mouse.kind {
+                MouseEventKind::ScrollDown if app.show_record_view || app.plan.is_some() => {
+                    app.select_node(-1)
+                }
+                MouseEventKind::ScrollUp if app.show_record_view || app.plan.is_some() => {
+                    app.select_node(1)
+                }
+                MouseEventKind::ScrollDown if !app.result_mode.is_grid() => app.select_row(-1),
+                MouseEventKind::ScrollUp if !app.result_mode.is_grid() => app.select_row(1),
                 MouseEventKind::ScrollDown => {
-                    app.table_state.offset_y = app.table_state.offset_y.saturating_sub(1);
+                    let state = &mut app.tab_mut().table_state;
+                    state.offset_y = state.offset_y.saturating_sub(1);
                 }
                 MouseEventKind::ScrollUp => {
-                    app.table_state.offset_y = app.table_state.offset_y.saturating_add(1);
+                    let state = &mut app.tab_mut().table_state;
+                    state.offset_y = state.offset_y.saturating_add(1);
                 }
                 MouseEventKind::ScrollRight => {
-                    app.table_state.offset_x = app.table_state.offset_x.saturating_sub(1);
+                    let state = &mut app.tab_mut().table_state;
+                    state.offset_x = state.offset_x.saturating_sub(1);
                 }
                 MouseEventKind::ScrollLeft => {
-                    app.table_state.offset_x = app.table_state.offset_x.saturating_add(1);
+                    let state = &mut app.tab_mut().table_state;
+                    state.offset_x = state.offset_x.saturating_add(1);
                 }
                 _ => {}
             },
             Event::Resize(_, _) => {}
+            Event::Paste(text) => handle_paste(text, &mut app),
         }
     }
 
@@ -0,0 +1,184 @@
+use duckdb::arrow::array::{Array, AsArray};
+use duckdb::arrow::datatypes::{DataType, Field};
+use duckdb::arrow::util::display::{ArrayFormatter, FormatOptions};
+use serde_json::Value;
+
+use crate::app::type_name;
+use crate::tree::TreeNode;
+
+/// Returns true for types that can be expanded into a tree.
+pub fn is_nested(data_type: &DataType) -> bool {
+    matches!(
+        data_type,
+        DataType::Struct(_)
+            | DataType::List(_)
+            | DataType::LargeList(_)
+            | DataType::FixedSizeList(_, _)
+            | DataType::Map(_, _)
+            | DataType::Union(_, _)
+    )
+}
+
+/// Returns true for columns of DuckDB's `JSON` type, which are exported
+/// as strings tagged with the `arrow.json` extension.
+pub fn is_json(field: &Field) -> bool {
+    field
+        .metadata()
+        .get("ARROW:extension:name")
+        .is_some_and(|name| name == "arrow.json")
+}
+
+/// Builds the tree for the value at `row` of `array`, described by `field`.
+///
+/// Nested values start collapsed. JSON values holding an object or array
+/// get their members as children.
+pub fn cell_node(
+    label: impl Into<String>,
+    field: &Field,
+    array: &dyn Array,
+    row: usize,
+) -> TreeNode {
+    let label = label.into();
+    let detail = type_name(array.data_type());
+
+    if array.is_null(row) {
+        return TreeNode::new(label, "NULL").detail(detail);
+    }
+
+    let value = format_value(array, row);
+    let children = match array.data_type() {
+        DataType::Struct(fields) => {
+            let array = array.as_struct();
+            fields
+                .iter()
+                .zip(array.columns())
+                .map(|(field, column)| cell_node(field.name().clone(), field, column.as_ref(), row))
+                .collect()
+        }
+        DataType::List(item) => list_children(item, array.as_list::<i32>().value(row).as_ref()),
+        DataType::LargeList(item) => {
+            list_children(item, array.as_list::<i64>().value(row).as_ref())
+        }
+        DataType::FixedSizeList(item, _) => {
+            list_children(item, array.as_fixed_size_list().value(row).as_ref())
+        }
+        DataType::Map(_, _) => {
+            let entries = array.as_map().value(row);
+            let (keys, values) = (entries.column(0), entries.column(1));
+            let value_field = &entries.fields()[1];
+            (0..entries.len())
+                .map(|i| {
+                    cell_node(
+                        format_value(keys.as_ref(), i),
+                        value_field,
+                        values.as_ref(),
+                        i,
+                    )
+                })
+                .collect()
+        }
+        DataType::Union(fields, _) => {
+            let array = array.as_union();
+            let type_id = array.type_id(row);
+            match fields.iter().find(|(id, _)| *id == type_id) {
+                Some((_, member)) => vec![cell_node(
+                    member.name().clone(),
+                    member,
+                    array.value(row).as_ref(),
+                    0,
+                )],
+                None => Vec::new(),
+            }
+        }
+        DataType::Utf8 | DataType::LargeUtf8 | DataType::Utf8View if is_json(field) => {
+            match serde_json::from_str::<Value>(&value) {
+                Ok(json @ (Value::Object(_) | Value::Array(_))) => json_children(&json),
+                _ => Vec::new(),
+            }
+        }
+        _ => Vec::new(),
+    };
+
+    TreeNode::new(label, value)
+        .detail(detail)
+        .children(children)
+}
+
+fn list_children(item: &Field, items: &dyn Array) -> Vec<TreeNode> {
+    (0..items.len())
+        .map(|i| cell_node(format!("[{}]", i + 1), item, items, i))
+        .collect()
+}
+
+fn json_children(json: &Value) -> Vec<TreeNode> {
+    match json {
+        Value::Object(map) => map.iter().map(|(k, v)| json_node(k.clone(), v)).collect(),
+        Value::Array(items) => items
+            .iter()
+            .enumerate()
+            .map(|(i, v)| json_node(format!("[{}]", i + 1), v))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn json_node(label: String, json: &Value) -> TreeNode {
+    let detail = match json {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    };
+    let value = match json {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    };
+    TreeNode::new(label, value)
+        .detail(detail)
+        .children(json_children(json))
+}
+
+fn format_value(array: &dyn Array, row: usize) -> String {
+    ArrayFormatter::try_new(array, &FormatOptions::default())
+        .map(|formatter| formatter.value(row).to_string())
+        .unwrap_or_default()
+}
+
+/// Builds a query that unnests or extracts the nested `column` of `source`, a
+/// relation usable in a `FROM` clause.
+pub fn unnest_query(source: &str, column: &str, data_type: &DataType) -> String {
+    let quoted = format!("\"{}\"", column.replace('"', "\"\""));
+    match data_type {
+        DataType::Struct(_)
+        | DataType::List(_)
+        | DataType::LargeList(_)
+        | DataType::FixedSizeList(_, _) => {
+            format!("SELECT * EXCLUDE ({quoted}), unnest({quoted})\nFROM {source};")
+        }
+        DataType::Map(_, _) => format!(
+            "SELECT * EXCLUDE ({quoted}), \
+             unnest(map_entries({quoted}), recursive := true)\nFROM {source};"
+        ),
+        DataType::Union(fields, _) => {
+            let alias =
+                |suffix: &str| format!("\"{}\"", format!("{column}_{suffix}").replace('"', "\"\""));
+            let members: String = fields
+                .iter()
+                .map(|(_, member)| {
+                    format!(
+                        ", union_extract({quoted}, '{}') AS {}",
+                        member.name().replace('\'', "''"),
+                        alias(member.name())
+                    )
+                })
+                .collect();
+            format!(
+                "SELECT * EXCLUDE ({quoted}), union_tag({quoted}) AS {}{members}\nFROM {source};",
+                alias("tag")
+            )
+        }
+        _ => format!("SELECT t.*, j.key, j.value\nFROM {source} t, json_each(t.{quoted}) j;"),
+    }
+}
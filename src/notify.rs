@@ -0,0 +1,83 @@
+use chrono::Local;
+
+/// Number of ticks a toast stays on screen.
+const TOAST_TICKS: u16 = 16;
+/// Number of ticks an error toast stays on screen.
+const ERROR_TICKS: u16 = 32;
+/// Number of toasts shown at once.
+const MAX_TOASTS: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    Info,
+    Success,
+    Warning,
+    Error,
+}
+
+impl Level {
+    pub fn name(self) -> &'static str {
+        match self {
+            Level::Info => "INFO",
+            Level::Success => "OK",
+            Level::Warning => "WARN",
+            Level::Error => "ERROR",
+        }
+    }
+}
+
+/// A message kept in the log.
+#[derive(Debug, Clone)]
+pub struct Notice {
+    pub level: Level,
+    pub message: String,
+    /// Statement that caused the message, if any.
+    pub statement: Option<String>,
+    /// Local time the message was logged, as `HH:MM:SS`.
+    pub time: String,
+}
+
+/// Message log with the toasts that are still on screen.
+#[derive(Debug, Default)]
+pub struct Notifications {
+    pub log: Vec<Notice>,
+    /// Log index and remaining ticks of each visible toast.
+    toasts: Vec<(usize, u16)>,
+}
+
+impl Notifications {
+    /// Logs a message and shows it as a toast.
+    pub fn push(&mut self, level: Level, message: impl Into<String>, statement: Option<&str>) {
+        self.log.push(Notice {
+            level,
+            message: message.into(),
+            statement: statement
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(str::to_string),
+            time: Local::now().format("%H:%M:%S").to_string(),
+        });
+        let ticks = if level == Level::Error {
+            ERROR_TICKS
+        } else {
+            TOAST_TICKS
+        };
+        self.toasts.push((self.log.len() - 1, ticks));
+        if self.toasts.len() > MAX_TOASTS {
+            self.toasts.remove(0);
+        }
+    }
+
+    /// Counts down the visible toasts, dropping the expired ones.
+    pub fn tick(&mut self) {
+        for (_, ticks) in &mut self.toasts {
+            *ticks = ticks.saturating_sub(1);
+        }
+        self.toasts.retain(|(_, ticks)| *ticks > 0);
+    }
+
+    /// Returns the visible toasts, oldest first.
+    pub fn toasts(&self) -> impl Iterator<Item = &Notice> {
+        self.toasts.iter().map(|(index, _)| &self.log[*index])
+    }
+}
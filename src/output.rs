@@ -0,0 +1,474 @@
+use std::fmt;
+use std::ops::Range;
+use std::str::FromStr;
+
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::Style,
+    widgets::{Block, StatefulWidget, Widget},
+};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use crate::custom_table::{gutter_width, Table, TableState};
+use crate::stats;
+
+/// How results are written as text, named like the modes of the DuckDB CLI.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Mode {
+    /// Columns in Unicode box-drawing borders.
+    Box,
+    /// Columns separated by spaces, with the headers underlined.
+    #[default]
+    Column,
+    Csv,
+    /// A JSON array of objects.
+    Json,
+    /// A JSON object per line.
+    JsonLines,
+    /// One `column = value` line per value, with records separated by a blank line.
+    Line,
+    /// Values separated by `|`.
+    List,
+    Markdown,
+    /// Columns in ASCII borders.
+    Table,
+    Tabs,
+}
+
+impl Mode {
+    pub const ALL: &[Mode] = &[
+        Mode::Box,
+        Mode::Column,
+        Mode::Csv,
+        Mode::Json,
+        Mode::JsonLines,
+        Mode::Line,
+        Mode::List,
+        Mode::Markdown,
+        Mode::Table,
+        Mode::Tabs,
+    ];
+}
+
+impl Mode {
+    /// Modes the results pane cycles through.
+    pub const SHOWN: &[Mode] = &[
+        Mode::Column,
+        Mode::Box,
+        Mode::Line,
+        Mode::Markdown,
+        Mode::Csv,
+        Mode::Json,
+    ];
+
+    /// Whether results are shown as a grid of cells, rather than as text.
+    pub fn is_grid(self) -> bool {
+        matches!(self, Self::Column | Self::Box)
+    }
+
+    /// The mode after this one in the results pane.
+    pub fn next(self) -> Self {
+        let i = Self::SHOWN.iter().position(|mode| *mode == self);
+        Self::SHOWN[i.map_or(0, |i| (i + 1) % Self::SHOWN.len())]
+    }
+}
+
+impl FromStr for Mode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.to_ascii_lowercase();
+        match name.as_str() {
+            "duckbox" => Ok(Self::Box),
+            "ascii" => Ok(Self::Table),
+            _ => Self::ALL
+                .iter()
+                .find(|mode| mode.to_string() == name)
+                .copied()
+                .ok_or_else(|| {
+                    let names: Vec<String> = Self::ALL.iter().map(Mode::to_string).collect();
+                    format!("expected one of {}, got {s:?}", names.join(", "))
+                }),
+        }
+    }
+}
+
+impl fmt::Display for Mode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Box => "box",
+            Self::Column => "column",
+            Self::Csv => "csv",
+            Self::Json => "json",
+            Self::JsonLines => "jsonlines",
+            Self::Line => "line",
+            Self::List => "list",
+            Self::Markdown => "markdown",
+            Self::Table => "table",
+            Self::Tabs => "tabs",
+        })
+    }
+}
+
+/// Text of a table, with the lines each row was written on.
+#[derive(Debug, Clone, Default)]
+pub struct Lines {
+    pub lines: Vec<String>,
+    /// Range of lines of each row.
+    pub rows: Vec<Range<usize>>,
+}
+
+impl Lines {
+    /// Adds a line that is not part of a row, such as a header.
+    fn line(&mut self, line: String) {
+        self.lines.extend(line.split('\n').map(str::to_string));
+    }
+
+    /// Adds the lines of a row. Values with line breaks take several lines.
+    fn row(&mut self, lines: impl IntoIterator<Item = String>) {
+        let start = self.lines.len();
+        for line in lines {
+            self.line(line);
+        }
+        self.rows.push(start..self.lines.len());
+    }
+}
+
+/// Writes `table` as text in `mode`, ending with a newline unless it is empty.
+pub fn render(table: &Table, mode: Mode) -> String {
+    let mut text = lines(table, mode).lines.join("\n");
+    if !text.is_empty() {
+        text.push('\n');
+    }
+    text
+}
+
+/// Writes `table` as lines of text in `mode`.
+pub fn lines(table: &Table, mode: Mode) -> Lines {
+    let mut lines = Lines::default();
+    if table.headers.is_empty() {
+        return lines;
+    }
+    match mode {
+        Mode::Box => bordered(table, ["┌─┬┐", "├─┼┤", "└─┴┘"], '│', &mut lines),
+        Mode::Table => bordered(table, ["+-++", "+-++", "+-++"], '|', &mut lines),
+        Mode::Column => {
+            let widths: Vec<usize> = table.widths.iter().map(|w| *w as usize).collect();
+            let row = |cells: &[String], header| {
+                let cells = cells_padded(table, cells, &widths, header);
+                cells.join(" ").trim_end().to_string()
+            };
+            lines.line(row(&table.headers, true));
+            let rules: Vec<String> = widths.iter().map(|width| "-".repeat(*width)).collect();
+            lines.line(rules.join(" "));
+            for cells in &table.rows {
+                lines.row([row(cells, false)]);
+            }
+        }
+        Mode::Markdown => {
+            let escaped = |cells: &[String]| -> Vec<String> {
+                cells
+                    .iter()
+                    .map(|cell| cell.replace('|', "\\|").replace('\n', "<br>"))
+                    .collect()
+            };
+            let rows: Vec<Vec<String>> = table.rows.iter().map(|cells| escaped(cells)).collect();
+            let headers = escaped(&table.headers);
+            let widths: Vec<usize> = (0..headers.len())
+                .map(|i| {
+                    std::iter::once(&headers)
+                        .chain(&rows)
+                        .map(|cells| cells[i].width())
+                        .max()
+                        .unwrap_or(0)
+                        .max(3)
+                })
+                .collect();
+            let row = |cells: &[String], header| {
+                let cells = cells_padded(table, cells, &widths, header);
+                format!("| {} |", cells.join(" | "))
+            };
+            lines.line(row(&headers, true));
+            let rules: Vec<String> = widths
+                .iter()
+                .enumerate()
+                .map(|(i, width)| {
+                    if is_numeric(table, i) {
+                        format!("{}:", "-".repeat(width - 1))
+                    } else {
+                        "-".repeat(*width)
+                    }
+                })
+                .collect();
+            lines.line(format!("| {} |", rules.join(" | ")));
+            for cells in &rows {
+                lines.row([row(cells, false)]);
+            }
+        }
+        Mode::Csv => {
+            let row = |cells: &[String]| {
+                let fields: Vec<String> = cells.iter().map(|cell| csv_field(cell)).collect();
+                fields.join(",")
+            };
+            lines.line(row(&table.headers));
+            for cells in &table.rows {
+                lines.row([row(cells)]);
+            }
+        }
+        Mode::Tabs | Mode::List => {
+            let separator = if mode == Mode::Tabs { "\t" } else { "|" };
+            lines.line(table.headers.join(separator));
+            for cells in &table.rows {
+                lines.row([cells.join(separator)]);
+            }
+        }
+        Mode::Line => {
+            let width = table.headers.iter().map(|h| h.width()).max().unwrap_or(0);
+            for (i, cells) in table.rows.iter().enumerate() {
+                if i > 0 {
+                    lines.line(String::new());
+                }
+                lines.row(table.headers.iter().zip(cells).map(|(header, value)| {
+                    let padding = " ".repeat(width - header.width());
+                    format!("{padding}{header} = {value}")
+                }));
+            }
+        }
+        Mode::Json => {
+            let last = table.rows.len().saturating_sub(1);
+            if table.rows.is_empty() {
+                lines.line("[]".to_string());
+            }
+            for (i, cells) in table.rows.iter().enumerate() {
+                let open = if i == 0 { "[" } else { "" };
+                let close = if i == last { "]" } else { "," };
+                lines.row([format!("{open}{}{close}", json_object(table, cells))]);
+            }
+        }
+        Mode::JsonLines => {
+            for cells in &table.rows {
+                lines.row([json_object(table, cells)]);
+            }
+        }
+    }
+    lines
+}
+
+/// Pads cells to `widths`, aligning numbers to the right below the headers.
+fn cells_padded(table: &Table, cells: &[String], widths: &[usize], header: bool) -> Vec<String> {
+    cells
+        .iter()
+        .zip(widths)
+        .enumerate()
+        .map(|(i, (cell, width))| {
+            let padding = " ".repeat(width.saturating_sub(cell.width()));
+            if !header && is_numeric(table, i) {
+                format!("{padding}{cell}")
+            } else {
+                format!("{cell}{padding}")
+            }
+        })
+        .collect()
+}
+
+/// Columns between borders drawn with the `top`, `middle` and `bottom`
+/// characters: the left end, the line, the crossing and the right end.
+fn bordered(table: &Table, [top, middle, bottom]: [&str; 3], bar: char, lines: &mut Lines) {
+    let widths: Vec<usize> = table.widths.iter().map(|w| *w as usize).collect();
+    let rule = |chars: &str| {
+        let chars: Vec<char> = chars.chars().collect();
+        let segments: Vec<String> = widths
+            .iter()
+            .map(|width| chars[1].to_string().repeat(width + 2))
+            .collect();
+        format!(
+            "{}{}{}",
+            chars[0],
+            segments.join(&chars[2].to_string()),
+            chars[3]
+        )
+    };
+    let row = |cells: &[String], header| {
+        let cells: Vec<String> = cells_padded(table, cells, &widths, header)
+            .into_iter()
+            .map(|cell| format!(" {cell} "))
+            .collect();
+        format!("{bar}{}{bar}", cells.join(&bar.to_string()))
+    };
+    lines.line(rule(top));
+    lines.line(row(&table.headers, true));
+    lines.line(rule(middle));
+    for cells in &table.rows {
+        lines.row([row(cells, false)]);
+    }
+    lines.line(rule(bottom));
+}
+
+/// Whether column `i` holds numbers, which are aligned to the right.
+pub fn is_numeric(table: &Table, i: usize) -> bool {
+    table
+        .types
+        .get(i)
+        .is_some_and(|type_name| stats::is_numeric(type_name))
+}
+
+/// Quotes a CSV field that holds a separator, quote or line break.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// A row as a JSON object, with numbers and booleans unquoted and empty
+/// values of other types than text as `null`.
+fn json_object(table: &Table, row: &[String]) -> String {
+    let fields: Vec<String> = table
+        .headers
+        .iter()
+        .zip(row)
+        .enumerate()
+        .map(|(i, (header, value))| {
+            let type_name = table.types.get(i).map_or("VARCHAR", String::as_str);
+            let value = if value.is_empty() && type_name != "VARCHAR" {
+                "null".to_string()
+            } else if is_numeric(table, i) || type_name == "BOOLEAN" {
+                value.clone()
+            } else {
+                serde_json::Value::from(value.as_str()).to_string()
+            };
+            format!("{}:{value}", serde_json::Value::from(header.as_str()))
+        })
+        .collect();
+    format!("{{{}}}", fields.join(","))
+}
+
+/// Results written as text, with row numbers in a gutter and the selected
+/// row highlighted.
+pub struct TextView<'a> {
+    lines: &'a Lines,
+    block: Option<Block<'a>>,
+    style: Style,
+    gutter_style: Style,
+    selected_style: Style,
+}
+
+impl<'a> TextView<'a> {
+    pub fn new(lines: &'a Lines) -> Self {
+        Self {
+            lines,
+            block: None,
+            style: Style::default(),
+            gutter_style: Style::default(),
+            selected_style: Style::default(),
+        }
+    }
+
+    pub fn block(mut self, block: Block<'a>) -> Self {
+        self.block = Some(block);
+        self
+    }
+
+    pub fn style(mut self, style: Style) -> Self {
+        self.style = style;
+        self
+    }
+
+    pub fn gutter_style(mut self, style: Style) -> Self {
+        self.gutter_style = style;
+        self
+    }
+
+    pub fn selected_style(mut self, style: Style) -> Self {
+        self.selected_style = style;
+        self
+    }
+}
+
+impl StatefulWidget for TextView<'_> {
+    type State = TableState;
+
+    /// Draws the lines from `offset_y`, scrolled right by `offset_x` columns.
+    ///
+    /// The offset is moved to keep the lines of the selected row in view.
+    fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+        let area = match self.block {
+            Some(block) => {
+                let inner = block.inner(area);
+                block.render(area, buf);
+                inner
+            }
+            None => area,
+        };
+        if area.width < 1 || area.height < 1 {
+            return;
+        }
+        buf.set_style(area, self.style);
+
+        let gutter = gutter_width(self.lines.rows.len());
+        let text_width = area.width.saturating_sub(gutter);
+        let height = area.height as usize;
+        state.visible_rows = area.height;
+        state.visible_width = text_width;
+
+        let selected = self.lines.rows.get(state.selected).cloned().unwrap_or(0..0);
+        let mut offset = state.offset_y;
+        if selected.start < offset {
+            offset = selected.start;
+        } else if selected.end > offset + height {
+            // Rows taller than the view show their start.
+            offset = selected.start.min(selected.end - height);
+        }
+        let offset = offset.min(self.lines.lines.len().saturating_sub(height));
+        state.offset_y = offset;
+
+        let mut row = self.lines.rows.partition_point(|lines| lines.end <= offset);
+        for (i, line) in self
+            .lines
+            .lines
+            .iter()
+            .enumerate()
+            .skip(offset)
+            .take(height)
+        {
+            let y = area.top() + (i - offset) as u16;
+            while self.lines.rows.get(row).is_some_and(|lines| lines.end <= i) {
+                row += 1;
+            }
+            let in_row = self.lines.rows.get(row).filter(|lines| lines.contains(&i));
+            if in_row.is_some_and(|lines| lines.start == i) {
+                let number = format!("{:>1$}", row + 1, gutter as usize - 1);
+                buf.set_stringn(area.left(), y, number, gutter as usize, self.gutter_style);
+            }
+            let line = line.replace('\t', "    ");
+            let visible = skip_columns(&line, state.offset_x as usize);
+            buf.set_stringn(
+                area.left() + gutter,
+                y,
+                visible,
+                text_width as usize,
+                self.style,
+            );
+            if in_row.is_some() && row == state.selected {
+                buf.set_style(
+                    Rect::new(area.left(), y, area.width, 1),
+                    self.selected_style,
+                );
+            }
+        }
+    }
+}
+
+/// The rest of `line` after its first `columns` columns.
+fn skip_columns(line: &str, columns: usize) -> &str {
+    let mut skipped = 0;
+    for (i, c) in line.char_indices() {
+        if skipped >= columns {
+            return &line[i..];
+        }
+        skipped += c.width().unwrap_or(0);
+    }
+    ""
+}
@@ -0,0 +1,201 @@
+use duckdb::types::Value;
+
+use crate::app::{App, AppResult};
+use crate::validate::statements;
+
+/// Name of the statement prepared to find the type of a parameter.
+const HINT_STATEMENT: &str = "quackmire_parameter_hint";
+
+/// A parameter of a query, such as `$1`, `?` or `$name`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Parameter {
+    /// Number or name the value is remembered by.
+    pub name: String,
+    /// How the parameter is written in the query, such as `$name` or `?2`.
+    pub label: String,
+    /// Type DuckDB expects, when it can tell from the query.
+    pub type_hint: Option<String>,
+}
+
+/// A parameter in the text of a query, at a byte range.
+#[derive(Debug, Clone, PartialEq)]
+struct Occurrence {
+    name: String,
+    label: String,
+    start: usize,
+    end: usize,
+}
+
+/// Finds the parameters written in `sql`, skipping strings, quoted
+/// identifiers, dollar-quoted strings and comments.
+///
+/// `?` parameters are numbered in order, as DuckDB does.
+fn occurrences(sql: &str) -> Vec<Occurrence> {
+    let mut found = Vec::new();
+    let mut positional = 0;
+    let mut chars = sql.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        let rest = &sql[i..];
+        let skip_to = |end: &str| {
+            rest[c.len_utf8()..]
+                .find(end)
+                .map(|at| i + c.len_utf8() + at + end.len())
+        };
+        let skipped = match c {
+            '\'' | '"' => Some(skip_to(if c == '\'' { "'" } else { "\"" })),
+            '-' if rest.starts_with("--") => Some(skip_to("\n")),
+            '/' if rest.starts_with("/*") => Some(skip_to("*/")),
+            '?' => {
+                positional += 1;
+                found.push(Occurrence {
+                    name: positional.to_string(),
+                    label: format!("?{positional}"),
+                    start: i,
+                    end: i + 1,
+                });
+                None
+            }
+            '$' => {
+                let word: String = rest[1..]
+                    .chars()
+                    .take_while(|c| c.is_alphanumeric() || *c == '_')
+                    .collect();
+                let end = i + 1 + word.len();
+                if sql[end..].starts_with('$') {
+                    // A dollar-quoted string, `$$...$$` or `$tag$...$tag$`.
+                    let tag = &sql[i..=end];
+                    Some(sql[end + 1..].find(tag).map(|at| end + 1 + at + tag.len()))
+                } else if !word.is_empty()
+                    && (word.chars().all(|c| c.is_ascii_digit())
+                        || !word.starts_with(|c: char| c.is_ascii_digit()))
+                {
+                    found.push(Occurrence {
+                        name: word.clone(),
+                        label: format!("${word}"),
+                        start: i,
+                        end,
+                    });
+                    Some(Some(end))
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        };
+        // Jump past what was skipped, to the end of the text when it is not closed.
+        if let Some(end) = skipped {
+            let end = end.unwrap_or(sql.len());
+            while chars.peek().is_some_and(|(j, _)| *j < end) {
+                chars.next();
+            }
+        }
+    }
+    found
+}
+
+/// Finds the parameters of `sql`, in the order they are bound.
+///
+/// Only single statements are checked, since preparing several runs all but
+/// the last.
+pub fn parameters(app: &App, sql: &str) -> AppResult<Vec<Parameter>> {
+    let found = occurrences(sql);
+    let count = statements(sql)
+        .iter()
+        .filter(|statement| !statement.text.trim().is_empty())
+        .count();
+    if found.is_empty() || count != 1 {
+        return Ok(Vec::new());
+    }
+    let statement = app.connection.prepare(sql)?;
+    let mut slots: Vec<Option<&Occurrence>> = vec![None; statement.parameter_count()];
+    for occurrence in &found {
+        let index = match occurrence.name.parse::<usize>() {
+            Ok(index) => Some(index),
+            Err(_) => statement.parameter_index(&occurrence.name)?,
+        };
+        if let Some(slot) = index.and_then(|index| slots.get_mut(index.wrapping_sub(1))) {
+            slot.get_or_insert(occurrence);
+        }
+    }
+    drop(statement);
+
+    let parameters = slots
+        .into_iter()
+        .enumerate()
+        .map(|(i, occurrence)| {
+            let (name, label) = occurrence.map_or_else(
+                || ((i + 1).to_string(), format!("${}", i + 1)),
+                |occurrence| (occurrence.name.clone(), occurrence.label.clone()),
+            );
+            let type_hint = type_hint(app, sql, &found, &name);
+            Parameter {
+                name,
+                label,
+                type_hint,
+            }
+        })
+        .collect();
+    Ok(parameters)
+}
+
+/// Asks DuckDB for the type of the parameter called `name`.
+///
+/// `duckdb_prepared_statements()` lists parameter types without saying which
+/// is which, so the query is prepared with the other parameters set to `NULL`.
+fn type_hint(app: &App, sql: &str, found: &[Occurrence], name: &str) -> Option<String> {
+    let mut single = String::new();
+    let mut last = 0;
+    for occurrence in found {
+        single.push_str(&sql[last..occurrence.start]);
+        single.push_str(if occurrence.name == name {
+            "$1"
+        } else {
+            "NULL"
+        });
+        last = occurrence.end;
+    }
+    single.push_str(&sql[last..]);
+    let single = single.trim().trim_end_matches(';');
+
+    app.connection
+        .execute_batch(&format!("PREPARE {HINT_STATEMENT} AS {single}"))
+        .ok()?;
+    let table = app.query_table(&format!(
+        "SELECT parameter_types[1] FROM duckdb_prepared_statements() WHERE name = '{HINT_STATEMENT}'"
+    ));
+    let _ = app
+        .connection
+        .execute_batch(&format!("DEALLOCATE {HINT_STATEMENT}"));
+    let hint = table.ok()?.rows.first()?.first()?.clone();
+    (!hint.is_empty() && hint != "UNKNOWN" && hint != "NULL").then_some(hint)
+}
+
+/// Converts a value typed for a parameter to a value of its type.
+///
+/// `NULL` is a null. Other values, including those that don't parse as the
+/// expected type, are bound as text for DuckDB to convert or reject.
+pub fn value(input: &str, type_hint: Option<&str>) -> Value {
+    let trimmed = input.trim();
+    if trimmed.eq_ignore_ascii_case("null") {
+        return Value::Null;
+    }
+    let text = || Value::Text(input.to_string());
+    let integer = || trimmed.parse().map(Value::BigInt);
+    let float = || trimmed.parse().map(Value::Double);
+    let Some(type_hint) = type_hint else {
+        return integer().or_else(|_| float()).unwrap_or_else(|_| text());
+    };
+    let type_hint = type_hint.to_ascii_uppercase();
+    match type_hint.as_str() {
+        "BOOLEAN" => match trimmed.to_ascii_lowercase().as_str() {
+            "true" | "t" | "yes" | "1" => Value::Boolean(true),
+            "false" | "f" | "no" | "0" => Value::Boolean(false),
+            _ => text(),
+        },
+        "TINYINT" | "SMALLINT" | "INTEGER" | "BIGINT" | "UTINYINT" | "USMALLINT" | "UINTEGER" => {
+            integer().unwrap_or_else(|_| text())
+        }
+        "FLOAT" | "DOUBLE" => float().unwrap_or_else(|_| text()),
+        _ => text(),
+    }
+}
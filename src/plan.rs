@@ -0,0 +1,164 @@
+use std::fs;
+
+use duckdb::arrow::array::RecordBatch;
+use duckdb::Connection;
+use ratatui::style::{Modifier, Style};
+use serde_json::Value;
+
+use crate::app::AppResult;
+use crate::tree::TreeNode;
+use crate::ui::{RED, YELLOW};
+
+/// Share of the total time from which an operator is shown as hot.
+const HOT: f64 = 25.0;
+/// Share of the total time from which an operator is shown as warm.
+const WARM: f64 = 10.0;
+
+/// Profiled operator tree of a query.
+#[derive(Debug, Clone, Default)]
+pub struct Plan {
+    pub query: String,
+    /// Total time of all operators, in seconds.
+    pub total: f64,
+    pub nodes: Vec<TreeNode>,
+}
+
+/// Returns the query profiled by an `EXPLAIN ANALYZE` statement.
+///
+/// The two keywords can be in any case and separated by any whitespace,
+/// including line breaks.
+pub fn analyzed_query(sql: &str) -> Option<&str> {
+    let (explain, rest) = next_word(sql)?;
+    let (analyze, query) = next_word(rest)?;
+    let query = query.trim_start();
+    (explain.eq_ignore_ascii_case("EXPLAIN")
+        && analyze.eq_ignore_ascii_case("ANALYZE")
+        && !query.is_empty())
+    .then_some(query)
+}
+
+/// Splits the first whitespace-separated word off `text`.
+fn next_word(text: &str) -> Option<(&str, &str)> {
+    let text = text.trim_start();
+    let end = text.find(char::is_whitespace).unwrap_or(text.len());
+    (end > 0).then(|| text.split_at(end))
+}
+
+impl Plan {
+    /// Runs `sql` with JSON profiling enabled and reads back the operator tree.
+    pub fn load(connection: &Connection, sql: &str) -> AppResult<Self> {
+        let path = std::env::temp_dir().join(format!("quackmire-plan-{}.json", std::process::id()));
+        connection.execute_batch(&format!(
+            "PRAGMA enable_profiling = 'json'; PRAGMA profiling_output = '{}';",
+            path.display().to_string().replace('\'', "''")
+        ))?;
+        let run = || -> AppResult<String> {
+            let mut stmt = connection.prepare(sql)?;
+            let _: Vec<RecordBatch> = stmt.query_arrow([])?.collect();
+            drop(stmt);
+            Ok(fs::read_to_string(&path)?)
+        };
+        let profile = run();
+        connection.execute_batch("PRAGMA disable_profiling;")?;
+        let _ = fs::remove_file(&path);
+        let profile: Value = serde_json::from_str(&profile?)?;
+
+        let operators = profile["children"].as_array().cloned().unwrap_or_default();
+        let total = match operators.iter().map(total_timing).sum::<f64>() {
+            t if t > 0.0 => t,
+            _ => number(&profile, &["latency", "timing"]),
+        };
+        let hottest = operators.iter().map(max_timing).fold(0.0_f64, f64::max);
+        let nodes = operators
+            .iter()
+            .map(|op| operator_node(op, total, hottest))
+            .collect();
+
+        Ok(Self {
+            query: sql.to_string(),
+            total,
+            nodes,
+        })
+    }
+}
+
+/// Returns the first numeric field among `keys`, which differ between DuckDB versions.
+fn number(value: &Value, keys: &[&str]) -> f64 {
+    keys.iter()
+        .find_map(|key| value[*key].as_f64())
+        .unwrap_or_default()
+}
+
+fn timing(op: &Value) -> f64 {
+    number(op, &["operator_timing", "timing"])
+}
+
+fn children(op: &Value) -> &[Value] {
+    op["children"]
+        .as_array()
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+}
+
+fn total_timing(op: &Value) -> f64 {
+    timing(op) + children(op).iter().map(total_timing).sum::<f64>()
+}
+
+fn max_timing(op: &Value) -> f64 {
+    children(op)
+        .iter()
+        .map(max_timing)
+        .fold(timing(op), f64::max)
+}
+
+fn extra_info(op: &Value) -> String {
+    match &op["extra_info"] {
+        Value::String(info) => info.split_whitespace().collect::<Vec<_>>().join(" "),
+        Value::Object(info) => info
+            .iter()
+            .map(|(key, value)| match value {
+                Value::String(value) => format!("{key}: {}", value.replace('\n', ", ")),
+                value => format!("{key}: {value}"),
+            })
+            .collect::<Vec<_>>()
+            .join(" | "),
+        _ => String::new(),
+    }
+}
+
+fn operator_node(op: &Value, total: f64, hottest: f64) -> TreeNode {
+    let name = ["operator_type", "operator_name", "name"]
+        .iter()
+        .find_map(|key| op[*key].as_str())
+        .unwrap_or("?")
+        .trim();
+    let timing = timing(op);
+    let cardinality = number(op, &["operator_cardinality", "cardinality"]) as u64;
+    let share = if total > 0.0 {
+        timing / total * 100.0
+    } else {
+        0.0
+    };
+
+    let style = if timing > 0.0 && (timing >= hottest || share >= HOT) {
+        Style::default().fg(RED).add_modifier(Modifier::BOLD)
+    } else if share >= WARM {
+        Style::default().fg(YELLOW)
+    } else {
+        Style::default()
+    };
+
+    TreeNode::new(
+        name,
+        format!("{:.3} ms  {}", timing * 1000.0, extra_info(op)),
+    )
+    .detail(format!("{share:5.1}%  {cardinality} rows"))
+    .style(style)
+    .expanded(true)
+    .children(
+        children(op)
+            .iter()
+            .map(|child| operator_node(child, total, hottest))
+            .collect(),
+    )
+}
@@ -1,7 +1,9 @@
 use ratatui::layout::{Constraint, Direction, Flex, Layout, Rect};
-use ratatui::style::{Color, Style};
-use ratatui::text::Text;
-use ratatui::widgets::{Block, Borders, Clear, Paragraph};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span, Text};
+use ratatui::widgets::{Block, Borders, Clear, Paragraph, Wrap};
+use std::fs;
+use std::path::{Path, PathBuf};
 
 fn popup_area(area: Rect, percent_x: u16, percent_y: u16) -> Rect {
     let vertical = Layout::vertical([Constraint::Percentage(percent_y)]).flex(Flex::Center);
@@ -11,15 +13,168 @@ fn popup_area(area: Rect, percent_x: u16, percent_y: u16) -> Rect {
     area
 }
 
+/// Popup asking for the path to save a buffer to.
+///
+/// Relative paths are resolved against `dir`, the explorer's current directory.
 #[derive(Debug)]
 pub struct FileNamePopup {
     pub input: String,
+    pub dir: PathBuf,
+    /// Candidates from the last completion that matched more than one entry.
+    pub completions: Vec<String>,
+    /// Existing file that the next confirmation overwrites.
+    pub overwrite: Option<PathBuf>,
 }
 
 impl FileNamePopup {
     pub fn new() -> Self {
         Self {
             input: String::new(),
+            dir: PathBuf::new(),
+            completions: Vec::new(),
+            overwrite: None,
+        }
+    }
+
+    /// Resets the popup to save into `dir`, starting with `input`.
+    pub fn open(&mut self, dir: PathBuf, input: String) {
+        self.dir = dir;
+        self.input = input;
+        self.completions.clear();
+        self.overwrite = None;
+    }
+
+    pub fn push(&mut self, c: char) {
+        self.input.push(c);
+        self.completions.clear();
+        self.overwrite = None;
+    }
+
+    pub fn push_str(&mut self, s: &str) {
+        self.input.push_str(s);
+        self.completions.clear();
+        self.overwrite = None;
+    }
+
+    pub fn pop(&mut self) {
+        self.input.pop();
+        self.completions.clear();
+        self.overwrite = None;
+    }
+
+    /// Returns the path the input refers to.
+    pub fn path(&self) -> PathBuf {
+        let input = Path::new(&self.input);
+        if input.is_absolute() {
+            input.to_path_buf()
+        } else {
+            self.dir.join(input)
+        }
+    }
+
+    /// Completes the last path component from the entries of its directory.
+    pub fn complete(&mut self) {
+        let (parent, prefix) = match self.input.rfind('/') {
+            Some(i) => (&self.input[..=i], &self.input[i + 1..]),
+            None => ("", self.input.as_str()),
+        };
+        let dir = if Path::new(parent).is_absolute() {
+            PathBuf::from(parent)
+        } else {
+            self.dir.join(parent)
+        };
+        let Ok(entries) = fs::read_dir(dir) else {
+            return;
+        };
+        let mut matches: Vec<String> = entries
+            .filter_map(|entry| {
+                let entry = entry.ok()?;
+                let mut name = entry.file_name().to_string_lossy().into_owned();
+                if !name.starts_with(prefix) || (prefix.is_empty() && name.starts_with('.')) {
+                    return None;
+                }
+                if entry.file_type().ok()?.is_dir() {
+                    name.push('/');
+                }
+                Some(name)
+            })
+            .collect();
+        matches.sort();
+
+        let Some(first) = matches.first() else {
+            return;
+        };
+        let common = matches.iter().fold(first.clone(), |common, name| {
+            common
+                .chars()
+                .zip(name.chars())
+                .take_while(|(a, b)| a == b)
+                .map(|(a, _)| a)
+                .collect()
+        });
+        self.input = format!("{parent}{common}");
+        self.overwrite = None;
+        self.completions = if matches.len() > 1 {
+            matches
+        } else {
+            Vec::new()
+        };
+    }
+
+    pub fn render(&self, frame: &mut ratatui::Frame) {
+        let area = frame.area();
+        let popup_area = popup_area(area, 60, 30);
+        frame.render_widget(Clear, popup_area);
+        frame.render_widget(
+            Block::default()
+                .title("Save File (Tab to complete)")
+                .borders(Borders::ALL)
+                .style(Style::default().bg(Color::DarkGray)),
+            popup_area,
+        );
+
+        let inner_area = Rect::new(
+            popup_area.x + 2,
+            popup_area.y + 2,
+            popup_area.width - 4,
+            popup_area.height - 4,
+        );
+        let mut lines = vec![
+            Line::raw(format!("In: {}", self.dir.display())),
+            Line::raw(format!("File name: {}", self.input)),
+        ];
+        if let Some(path) = &self.overwrite {
+            lines.push(Line::styled(
+                format!("{} exists, press Enter again to overwrite", path.display()),
+                Style::default().fg(Color::Yellow),
+            ));
+        }
+        if !self.completions.is_empty() {
+            lines.push(Line::raw(self.completions.join("  ")));
+        }
+        frame.render_widget(
+            Paragraph::new(lines)
+                .wrap(Wrap { trim: true })
+                .style(Style::default().fg(Color::White)),
+            inner_area,
+        );
+    }
+}
+
+/// Single-line text input shown in a popup.
+#[derive(Debug)]
+pub struct InputPopup {
+    pub title: String,
+    pub label: String,
+    pub input: String,
+}
+
+impl InputPopup {
+    pub fn new(title: &str, label: &str) -> Self {
+        Self {
+            title: title.to_string(),
+            label: label.to_string(),
+            input: String::new(),
         }
     }
 
@@ -29,7 +184,7 @@ impl FileNamePopup {
         frame.render_widget(Clear, popup_area);
         frame.render_widget(
             Block::default()
-                .title("Save File")
+                .title(self.title.as_str())
                 .borders(Borders::ALL)
                 .style(Style::default().bg(Color::DarkGray)),
             popup_area,
@@ -42,9 +197,257 @@ impl FileNamePopup {
             popup_area.height - 4,
         );
         frame.render_widget(
-            Paragraph::new(Text::raw(&format!("File name: {}", self.input)))
+            Paragraph::new(Text::raw(format!("{}: {}", self.label, self.input)))
                 .style(Style::default().fg(Color::White)),
             inner_area,
         );
     }
 }
+
+/// Popup with labelled single-line fields, one of which has focus.
+#[derive(Debug)]
+pub struct FormPopup {
+    pub title: String,
+    /// Labels and values of the fields.
+    pub fields: Vec<(String, String)>,
+    pub focused: usize,
+}
+
+impl FormPopup {
+    pub fn new(title: &str, labels: &[&str]) -> Self {
+        Self {
+            title: title.to_string(),
+            fields: labels
+                .iter()
+                .map(|label| (label.to_string(), String::new()))
+                .collect(),
+            focused: 0,
+        }
+    }
+
+    /// Resets the fields to `values` and focuses the first one.
+    pub fn open(&mut self, values: Vec<String>) {
+        for (field, value) in self.fields.iter_mut().zip(values) {
+            field.1 = value;
+        }
+        self.focused = 0;
+    }
+
+    pub fn value(&self, index: usize) -> &str {
+        self.fields
+            .get(index)
+            .map_or("", |(_, value)| value.as_str())
+    }
+
+    pub fn input(&mut self) -> &mut String {
+        &mut self.fields[self.focused].1
+    }
+
+    /// Moves the focus by `amount` fields, wrapping around.
+    pub fn focus(&mut self, amount: isize) {
+        let count = self.fields.len() as isize;
+        self.focused = (self.focused as isize + amount).rem_euclid(count) as usize;
+    }
+
+    pub fn render(&self, frame: &mut ratatui::Frame) {
+        let area = frame.area();
+        let popup_area = popup_area(area, 60, 30);
+        frame.render_widget(Clear, popup_area);
+        frame.render_widget(
+            Block::default()
+                .title(format!("{} (Tab for next field)", self.title))
+                .borders(Borders::ALL)
+                .style(Style::default().bg(Color::DarkGray)),
+            popup_area,
+        );
+
+        let inner_area = Rect::new(
+            popup_area.x + 2,
+            popup_area.y + 2,
+            popup_area.width.saturating_sub(4),
+            popup_area.height.saturating_sub(4),
+        );
+        let lines: Vec<Line> = self
+            .fields
+            .iter()
+            .enumerate()
+            .map(|(i, (label, value))| {
+                if i == self.focused {
+                    Line::styled(
+                        format!("{label}: {value}_"),
+                        Style::default()
+                            .fg(Color::White)
+                            .add_modifier(Modifier::BOLD),
+                    )
+                } else {
+                    Line::styled(
+                        format!("{label}: {value}"),
+                        Style::default().fg(Color::Gray),
+                    )
+                }
+            })
+            .collect();
+        frame.render_widget(Paragraph::new(lines), inner_area);
+    }
+}
+
+/// An entry of a [`PickerPopup`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct PickerItem {
+    pub name: String,
+    pub detail: String,
+}
+
+/// Popup listing items to pick from, filtered by what is typed.
+#[derive(Debug)]
+pub struct PickerPopup {
+    pub title: String,
+    pub input: String,
+    pub items: Vec<PickerItem>,
+    /// Indices of the items matching `input`, best match first.
+    pub matches: Vec<usize>,
+    pub selected: usize,
+}
+
+impl PickerPopup {
+    pub fn new(title: &str) -> Self {
+        Self {
+            title: title.to_string(),
+            input: String::new(),
+            items: Vec::new(),
+            matches: Vec::new(),
+            selected: 0,
+        }
+    }
+
+    /// Resets the popup to list `items`.
+    pub fn open(&mut self, items: Vec<PickerItem>) {
+        self.items = items;
+        self.input.clear();
+        self.filter();
+    }
+
+    pub fn push(&mut self, c: char) {
+        self.input.push(c);
+        self.filter();
+    }
+
+    pub fn push_str(&mut self, s: &str) {
+        self.input.push_str(s);
+        self.filter();
+    }
+
+    pub fn pop(&mut self) {
+        self.input.pop();
+        self.filter();
+    }
+
+    /// Finds the items matching the input, best first.
+    fn filter(&mut self) {
+        let mut scored: Vec<(i64, usize)> = self
+            .items
+            .iter()
+            .enumerate()
+            .filter_map(|(i, item)| {
+                let name = fuzzy_score(&self.input, &item.name);
+                let detail = fuzzy_score(&self.input, &item.detail).map(|score| score / 2);
+                Some((name.max(detail)?, i))
+            })
+            .collect();
+        // Stable, so equally good matches keep the order of the items.
+        scored.sort_by_key(|(score, _)| -score);
+        self.matches = scored.into_iter().map(|(_, i)| i).collect();
+        self.selected = 0;
+    }
+
+    /// Moves the selection by `amount`, wrapping around.
+    pub fn select(&mut self, amount: isize) {
+        let count = self.matches.len() as isize;
+        if count > 0 {
+            self.selected = (self.selected as isize + amount).rem_euclid(count) as usize;
+        }
+    }
+
+    /// Index of the selected item in `items`.
+    pub fn selected_item(&self) -> Option<usize> {
+        self.matches.get(self.selected).copied()
+    }
+
+    pub fn render(&self, frame: &mut ratatui::Frame) {
+        let area = frame.area();
+        let popup_area = popup_area(area, 60, 60);
+        frame.render_widget(Clear, popup_area);
+        frame.render_widget(
+            Block::default()
+                .title(format!(
+                    "{} ({}/{})",
+                    self.title,
+                    self.matches.len(),
+                    self.items.len()
+                ))
+                .borders(Borders::ALL)
+                .style(Style::default().bg(Color::DarkGray)),
+            popup_area,
+        );
+
+        let inner_area = Rect::new(
+            popup_area.x + 2,
+            popup_area.y + 2,
+            popup_area.width.saturating_sub(4),
+            popup_area.height.saturating_sub(4),
+        );
+        let height = (inner_area.height as usize).saturating_sub(2);
+        let offset = (self.selected + 1).saturating_sub(height);
+        let mut lines = vec![Line::raw(format!("> {}", self.input)), Line::raw("")];
+        lines.extend(
+            self.matches
+                .iter()
+                .enumerate()
+                .skip(offset)
+                .take(height)
+                .map(|(n, &i)| {
+                    let item = &self.items[i];
+                    let style = if n == self.selected {
+                        Style::default().fg(Color::Black).bg(Color::Gray)
+                    } else {
+                        Style::default().fg(Color::White)
+                    };
+                    Line::from(vec![
+                        Span::styled(item.name.clone(), style.add_modifier(Modifier::BOLD)),
+                        Span::styled(format!("  {}", item.detail), style),
+                    ])
+                }),
+        );
+        frame.render_widget(Paragraph::new(lines), inner_area);
+    }
+}
+
+/// Scores how well `text` matches `pattern`, whose characters must appear in
+/// it in order, ignoring case.
+///
+/// Consecutive characters and characters at the start of words score higher.
+/// Returns `None` if `text` does not match.
+pub fn fuzzy_score(pattern: &str, text: &str) -> Option<i64> {
+    let text: Vec<char> = text.chars().flat_map(char::to_lowercase).collect();
+    let mut score = 0;
+    let mut position = 0;
+    let mut previous: Option<usize> = None;
+    for c in pattern.chars().flat_map(char::to_lowercase) {
+        if c.is_whitespace() {
+            continue;
+        }
+        let found = position + text[position..].iter().position(|&t| t == c)?;
+        score += 1;
+        if previous.is_some_and(|previous| previous + 1 == found) {
+            score += 5;
+        }
+        if found == 0 || !text[found - 1].is_alphanumeric() {
+            score += 3;
+        }
+        // Gaps cost a little, so tighter matches win.
+        score -= (found - position).min(5) as i64;
+        previous = Some(found);
+        position = found + 1;
+    }
+    Some(score)
+}
@@ -0,0 +1,304 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Modifier, Style},
+    widgets::{Block, Sparkline, StatefulWidget, Widget},
+};
+use unicode_width::UnicodeWidthStr;
+
+use crate::app::{App, AppResult};
+use crate::stats::{base_type, histogram_query, is_numeric};
+
+/// Number of bins of the numeric histograms.
+const BINS: usize = 24;
+/// Number of most frequent values kept for categorical columns.
+const TOP_VALUES: usize = 5;
+
+const CATEGORICAL_TYPES: [&str; 3] = ["VARCHAR", "BOOLEAN", "ENUM"];
+
+/// Summary statistics of a single column.
+#[derive(Debug, Clone, Default)]
+pub struct ColumnProfile {
+    pub name: String,
+    pub column_type: String,
+    pub min: String,
+    pub max: String,
+    pub approx_unique: String,
+    pub null_percentage: String,
+    pub q25: String,
+    pub q50: String,
+    pub q75: String,
+    pub histogram: Vec<u64>,
+    pub top_values: Vec<(String, u64)>,
+}
+
+/// Column profile of a query or file, computed with `SUMMARIZE`.
+#[derive(Debug, Clone, Default)]
+pub struct Profile {
+    /// Description of what was profiled, shown as the panel title.
+    pub title: String,
+    pub columns: Vec<ColumnProfile>,
+}
+
+impl Profile {
+    /// Profiles `source`, a relation usable in a `FROM` clause.
+    ///
+    /// `SUMMARIZE` is followed by one query computing the histograms and most
+    /// frequent values of all columns, so `source` is read twice in total.
+    pub fn load(app: &App, title: String, source: &str) -> AppResult<Self> {
+        let summary = app.query_table(&format!("SUMMARIZE SELECT * FROM {source}"))?;
+
+        let mut columns: Vec<ColumnProfile> = (0..summary.rows.len())
+            .map(|row| {
+                let value = |name: &str| summary.value(row, name).unwrap_or_default().to_string();
+                ColumnProfile {
+                    name: value("column_name"),
+                    column_type: value("column_type"),
+                    min: value("min"),
+                    max: value("max"),
+                    approx_unique: value("approx_unique"),
+                    null_percentage: value("null_percentage"),
+                    q25: value("q25"),
+                    q50: value("q50"),
+                    q75: value("q75"),
+                    ..Default::default()
+                }
+            })
+            .collect();
+
+        let mut details = Vec::new();
+        for (i, column) in columns.iter_mut().enumerate() {
+            let quoted = format!("\"{}\"", column.name.replace('"', "\"\""));
+            if is_numeric(&column.column_type) {
+                column.histogram = vec![0; BINS];
+                details.push(format!(
+                    "SELECT {i} AS col, bin::VARCHAR AS v, n FROM ({})",
+                    histogram_query(&format!("{quoted}::DOUBLE"), source, BINS)
+                ));
+            } else if CATEGORICAL_TYPES.contains(&base_type(&column.column_type)) {
+                details.push(format!(
+                    "SELECT * FROM (SELECT {i} AS col, {quoted}::VARCHAR AS v, count(*) AS n FROM {source} \
+                     GROUP BY v ORDER BY n DESC LIMIT {TOP_VALUES})"
+                ));
+            }
+        }
+        if details.is_empty() {
+            return Ok(Self { title, columns });
+        }
+
+        let details = app.query_table(&details.join("\nUNION ALL\n"))?;
+        for row in &details.rows {
+            let (Some(Ok(col)), Some(value), Some(Ok(n))) = (
+                row.first().map(|c| c.parse::<usize>()),
+                row.get(1),
+                row.get(2).map(|n| n.parse::<u64>()),
+            ) else {
+                continue;
+            };
+            let Some(column) = columns.get_mut(col) else {
+                continue;
+            };
+            if column.histogram.is_empty() {
+                column.top_values.push((value.clone(), n));
+            } else if let Ok(bin) = value.parse::<usize>() {
+                column.histogram[bin.min(BINS - 1)] = n;
+            }
+        }
+        for column in &mut columns {
+            column
+                .top_values
+                .sort_by_key(|(_, n)| std::cmp::Reverse(*n));
+        }
+
+        Ok(Self { title, columns })
+    }
+}
+
+impl ColumnProfile {
+    fn height(&self) -> u16 {
+        let extra = if !self.histogram.is_empty() {
+            1
+        } else {
+            self.top_values.len() as u16
+        };
+        3 + extra + 1
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ProfileState {
+    pub offset: usize,
+    pub selected: usize,
+}
+
+/// Panel showing one card per profiled column.
+pub struct ProfileView<'a> {
+    profile: &'a Profile,
+    block: Option<Block<'static>>,
+    style: Style,
+    name_style: Style,
+    label_style: Style,
+    chart_style: Style,
+}
+
+impl<'a> ProfileView<'a> {
+    pub fn new(profile: &'a Profile) -> Self {
+        Self {
+            profile,
+            block: None,
+            style: Style::default(),
+            name_style: Style::default(),
+            label_style: Style::default(),
+            chart_style: Style::default(),
+        }
+    }
+
+    pub fn block(mut self, block: Block<'static>) -> Self {
+        self.block = Some(block);
+        self
+    }
+
+    pub fn style(mut self, style: Style) -> Self {
+        self.style = style;
+        self
+    }
+
+    pub fn name_style(mut self, style: Style) -> Self {
+        self.name_style = style;
+        self
+    }
+
+    pub fn label_style(mut self, style: Style) -> Self {
+        self.label_style = style;
+        self
+    }
+
+    pub fn chart_style(mut self, style: Style) -> Self {
+        self.chart_style = style;
+        self
+    }
+}
+
+impl StatefulWidget for ProfileView<'_> {
+    type State = ProfileState;
+
+    fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+        let area = match self.block {
+            Some(b) => {
+                let inner_area = b.inner(area);
+                b.render(area, buf);
+                inner_area
+            }
+            None => area,
+        };
+
+        if area.width < 1 || area.height < 1 || self.profile.columns.is_empty() {
+            return;
+        }
+
+        buf.set_style(area, self.style);
+
+        let columns = &self.profile.columns;
+        state.selected = state.selected.min(columns.len() - 1);
+        if state.selected < state.offset {
+            state.offset = state.selected;
+        }
+        while state.offset < state.selected
+            && columns[state.offset..=state.selected]
+                .iter()
+                .map(ColumnProfile::height)
+                .sum::<u16>()
+                > area.height
+        {
+            state.offset += 1;
+        }
+
+        let width = area.width as usize;
+        let mut y = area.top();
+        for (i, column) in columns.iter().enumerate().skip(state.offset) {
+            if y + 3 > area.bottom() {
+                break;
+            }
+            let name_style = if i == state.selected {
+                self.name_style.add_modifier(Modifier::REVERSED)
+            } else {
+                self.name_style
+            };
+            let (x, _) = buf.set_stringn(area.left(), y, &column.name, width, name_style);
+            buf.set_stringn(
+                x + 1,
+                y,
+                &column.column_type,
+                (area.right().saturating_sub(x + 1)) as usize,
+                self.label_style,
+            );
+            buf.set_stringn(
+                area.left() + 1,
+                y + 1,
+                format!(
+                    "{} … {}  nulls {}%  distinct ~{}",
+                    column.min, column.max, column.null_percentage, column.approx_unique
+                ),
+                width - 1,
+                self.style,
+            );
+            buf.set_stringn(
+                area.left() + 1,
+                y + 2,
+                format!("q25 {}  q50 {}  q75 {}", column.q25, column.q50, column.q75),
+                width - 1,
+                self.style,
+            );
+            y += 3;
+
+            if !column.histogram.is_empty() && y < area.bottom() {
+                let chart_area = Rect::new(
+                    area.left() + 1,
+                    y,
+                    (area.width - 1).min(column.histogram.len() as u16),
+                    1,
+                );
+                Sparkline::default()
+                    .data(&column.histogram)
+                    .style(self.chart_style)
+                    .render(chart_area, buf);
+                y += 1;
+            }
+
+            let max_count = column.top_values.iter().map(|(_, n)| *n).max().unwrap_or(1);
+            let label_width = column
+                .top_values
+                .iter()
+                .map(|(v, _)| v.width())
+                .max()
+                .unwrap_or(0)
+                .min(width / 2);
+            for (value, count) in &column.top_values {
+                if y >= area.bottom() {
+                    break;
+                }
+                buf.set_stringn(area.left() + 1, y, value, label_width, self.style);
+                let bar_x = area.left() + 2 + label_width as u16;
+                let bar_room = area.right().saturating_sub(bar_x) as usize;
+                let count_text = format!(" {count}");
+                let bar_len = (bar_room.saturating_sub(count_text.width()) as u64 * count
+                    / max_count.max(1)) as usize;
+                let (x, _) =
+                    buf.set_stringn(bar_x, y, "▇".repeat(bar_len), bar_room, self.chart_style);
+                buf.set_stringn(
+                    x,
+                    y,
+                    count_text,
+                    area.right().saturating_sub(x) as usize,
+                    self.label_style,
+                );
+                y += 1;
+            }
+
+            y += 1;
+            if y >= area.bottom() {
+                break;
+            }
+        }
+    }
+}
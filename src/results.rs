@@ -0,0 +1,140 @@
+use std::time::Duration;
+
+use duckdb::arrow::array::{ArrayRef, RecordBatch};
+use duckdb::arrow::datatypes::Field;
+use duckdb::arrow::error::ArrowError;
+use duckdb::Connection;
+
+use crate::app::{type_name, AppResult};
+use crate::custom_table::{Table, TableState};
+use crate::nested::is_json;
+
+/// A result set shown as a tab over the results pane.
+#[derive(Debug, Clone)]
+pub struct ResultTab {
+    pub name: String,
+    /// SQL that produced the results.
+    pub query: String,
+    pub elapsed: Duration,
+    pub results: Vec<RecordBatch>,
+    pub table: Table,
+    pub table_state: TableState,
+    /// Pinned tabs are kept when the next query runs.
+    pub pinned: bool,
+    /// Temp table holding a copy of the results while the tab is pinned, or
+    /// for as long as the tab exists when its query had parameters.
+    pub temp_table: Option<String>,
+    /// Whether values were bound to parameters of the query, so it can't be run
+    /// again on its own.
+    pub parameterized: bool,
+}
+
+impl ResultTab {
+    /// Creates a tab without results.
+    pub fn empty(name: String) -> Self {
+        Self {
+            name,
+            query: String::new(),
+            elapsed: Duration::ZERO,
+            results: Vec::new(),
+            table: Table::default(),
+            table_state: TableState::default(),
+            pinned: false,
+            temp_table: None,
+            parameterized: false,
+        }
+    }
+
+    pub fn new(
+        name: String,
+        query: String,
+        elapsed: Duration,
+        results: Vec<RecordBatch>,
+    ) -> Result<Self, ArrowError> {
+        Ok(Self {
+            table: Table::from_batches(&results)?,
+            query,
+            elapsed,
+            results,
+            ..Self::empty(name)
+        })
+    }
+
+    /// Returns the field, the column array and the row within it for a result cell.
+    pub fn cell(&self, row: usize, col: usize) -> Option<(&Field, &ArrayRef, usize)> {
+        let mut row = row;
+        for batch in &self.results {
+            if row < batch.num_rows() {
+                let field = batch.schema_ref().fields().get(col)?;
+                return batch
+                    .columns()
+                    .get(col)
+                    .map(|array| (field.as_ref(), array, row));
+            }
+            row -= batch.num_rows();
+        }
+        None
+    }
+
+    /// Returns a relation over the results usable in a `FROM` clause.
+    ///
+    /// Tabs stored as a temp table read from it, so later queries see the same rows.
+    pub fn source(&self) -> String {
+        match &self.temp_table {
+            Some(name) => format!("\"{}\"", name.replace('"', "\"\"")),
+            None => format!("({})", self.query.trim().trim_end_matches(';')),
+        }
+    }
+
+    /// Copies the results into a new temp table `name`, failing if a table of
+    /// that name exists.
+    ///
+    /// The rows are appended from the fetched batches, so the query is not run
+    /// again and the table holds exactly the rows shown.
+    pub fn store(&self, connection: &Connection, name: &str) -> AppResult<()> {
+        let Some(first) = self.results.first() else {
+            return Err("There are no rows to store".into());
+        };
+        let columns: Vec<String> = first
+            .schema()
+            .fields()
+            .iter()
+            .map(|field| {
+                let data_type = if is_json(field) {
+                    "JSON".to_string()
+                } else {
+                    type_name(field.data_type())
+                };
+                format!("\"{}\" {data_type}", field.name().replace('"', "\"\""))
+            })
+            .collect();
+        let quoted = format!("\"{}\"", name.replace('"', "\"\""));
+        connection.execute_batch(&format!(
+            "CREATE TEMP TABLE {quoted} ({})",
+            columns.join(", ")
+        ))?;
+        let appended = connection.appender(name).and_then(|mut appender| {
+            for batch in &self.results {
+                appender.append_record_batch(batch.clone())?;
+            }
+            appender.flush()
+        });
+        if let Err(e) = appended {
+            connection.execute_batch(&format!("DROP TABLE IF EXISTS {quoted}"))?;
+            return Err(e.into());
+        }
+        Ok(())
+    }
+
+    /// Title shown in the tab bar.
+    pub fn title(&self) -> String {
+        let mut title = self.name.clone();
+        if self.pinned {
+            title.push_str(" [pinned]");
+        }
+        if let Some(temp_table) = &self.temp_table {
+            title.push_str(&format!(" as {temp_table}"));
+        }
+        title
+    }
+}
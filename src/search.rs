@@ -0,0 +1,254 @@
+use regex::Regex;
+use tui_textarea::{CursorMove, TextArea};
+
+/// Input field of the search bar.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SearchField {
+    #[default]
+    Find,
+    Replace,
+}
+
+/// A match, from `start` to `end`, as rows and character columns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Match {
+    pub start: (usize, usize),
+    pub end: (usize, usize),
+}
+
+/// Find and replace bar of the query editor.
+///
+/// Matches are highlighted by the `TextArea` and the current one is selected.
+/// When the bar is opened over a selection, only matches inside it are counted
+/// and replaced.
+#[derive(Debug, Clone, Default)]
+pub struct SearchBar {
+    pub find: String,
+    pub replace: String,
+    pub field: SearchField,
+    /// Whether `find` is a regular expression rather than literal text.
+    pub regex: bool,
+    pub case_sensitive: bool,
+    /// Selection the search is limited to.
+    pub scope: Option<Match>,
+    /// Cursor position when the bar was opened, where incremental search starts.
+    origin: (usize, usize),
+    pub matches: Vec<Match>,
+    pub current: Option<usize>,
+    /// Why `find` is not a valid pattern.
+    pub error: Option<String>,
+}
+
+impl SearchBar {
+    /// Resets the search to start at the cursor, within the selection if there is one.
+    pub fn open(&mut self, textarea: &mut TextArea<'static>) {
+        self.scope = textarea
+            .selection_range()
+            .filter(|(start, end)| start != end)
+            .map(|(start, end)| Match { start, end });
+        textarea.cancel_selection();
+        self.origin = self.scope.map_or(textarea.cursor(), |scope| scope.start);
+        self.field = SearchField::Find;
+        self.update(textarea);
+    }
+
+    /// Removes the highlighting and the selected match.
+    pub fn close(&mut self, textarea: &mut TextArea<'static>) {
+        let _ = textarea.set_search_pattern("");
+        textarea.cancel_selection();
+        self.matches.clear();
+        self.current = None;
+    }
+
+    pub fn input(&mut self) -> &mut String {
+        match self.field {
+            SearchField::Find => &mut self.find,
+            SearchField::Replace => &mut self.replace,
+        }
+    }
+
+    /// Compiles `find` with the regex and case settings.
+    fn pattern(&self) -> Result<Option<Regex>, regex::Error> {
+        if self.find.is_empty() {
+            return Ok(None);
+        }
+        let pattern = if self.regex {
+            self.find.clone()
+        } else {
+            regex::escape(&self.find)
+        };
+        // The flag is part of the pattern, so the `TextArea` highlights the same matches.
+        let pattern = if self.case_sensitive {
+            pattern
+        } else {
+            format!("(?i){pattern}")
+        };
+        Regex::new(&pattern).map(Some)
+    }
+
+    /// Finds the matches again and selects the first one from the search origin.
+    pub fn update(&mut self, textarea: &mut TextArea<'static>) {
+        self.refresh(textarea);
+        let first = self
+            .matches
+            .iter()
+            .position(|m| m.start >= self.origin)
+            .or((!self.matches.is_empty()).then_some(0));
+        self.select(first, textarea);
+    }
+
+    /// Finds the matches, highlights them and checks the pattern.
+    fn refresh(&mut self, textarea: &mut TextArea<'static>) {
+        self.matches.clear();
+        self.current = None;
+        let pattern = match self.pattern() {
+            Ok(pattern) => {
+                self.error = None;
+                pattern
+            }
+            Err(e) => {
+                self.error = Some(e.to_string().lines().last().unwrap_or_default().to_string());
+                None
+            }
+        };
+        let Some(pattern) = pattern else {
+            let _ = textarea.set_search_pattern("");
+            return;
+        };
+        let _ = textarea.set_search_pattern(pattern.as_str());
+
+        for (row, line) in textarea.lines().iter().enumerate() {
+            for found in pattern.find_iter(line) {
+                if found.start() == found.end() {
+                    continue;
+                }
+                let column = |byte: usize| line[..byte].chars().count();
+                let m = Match {
+                    start: (row, column(found.start())),
+                    end: (row, column(found.end())),
+                };
+                if self
+                    .scope
+                    .is_none_or(|scope| scope.start <= m.start && m.end <= scope.end)
+                {
+                    self.matches.push(m);
+                }
+            }
+        }
+    }
+
+    /// Makes the match at `index` current and selects it in the editor.
+    fn select(&mut self, index: Option<usize>, textarea: &mut TextArea<'static>) {
+        self.current = index;
+        textarea.cancel_selection();
+        let Some(m) = index.and_then(|index| self.matches.get(index)) else {
+            return;
+        };
+        textarea.move_cursor(CursorMove::Jump(m.start.0 as u16, m.start.1 as u16));
+        textarea.start_selection();
+        textarea.move_cursor(CursorMove::Jump(m.end.0 as u16, m.end.1 as u16));
+    }
+
+    /// Moves to the next or previous match, wrapping around.
+    pub fn step(&mut self, forward: bool, textarea: &mut TextArea<'static>) {
+        let count = self.matches.len();
+        if count == 0 {
+            return;
+        }
+        let index = match (self.current, forward) {
+            (Some(current), true) => (current + 1) % count,
+            (Some(current), false) => (current + count - 1) % count,
+            (None, true) => 0,
+            (None, false) => count - 1,
+        };
+        self.origin = self.matches[index].start;
+        self.select(Some(index), textarea);
+    }
+
+    /// Text that replaces `m`, with `$1`-style groups expanded in regex mode.
+    fn replacement(&self, m: &Match, line: &str) -> String {
+        if !self.regex {
+            return self.replace.clone();
+        }
+        let Ok(Some(pattern)) = self.pattern() else {
+            return self.replace.clone();
+        };
+        let start = line
+            .char_indices()
+            .nth(m.start.1)
+            .map_or(line.len(), |(byte, _)| byte);
+        let mut text = String::new();
+        if let Some(captures) = pattern.captures_at(line, start) {
+            captures.expand(&self.replace, &mut text);
+        }
+        text
+    }
+
+    /// Replaces the current match and moves to the next one.
+    ///
+    /// Returns whether the text changed.
+    pub fn replace_current(&mut self, textarea: &mut TextArea<'static>) -> bool {
+        let Some(m) = self
+            .current
+            .and_then(|index| self.matches.get(index))
+            .copied()
+        else {
+            return false;
+        };
+        let replacement = self.replacement(&m, &textarea.lines()[m.start.0]);
+        self.select(self.current, textarea);
+        textarea.insert_str(&replacement);
+        self.shift_scope(&m, replacement.chars().count());
+        self.origin = textarea.cursor();
+        self.update(textarea);
+        true
+    }
+
+    /// Replaces all matches at once and returns how many there were.
+    pub fn replace_all(&mut self, textarea: &mut TextArea<'static>) -> usize {
+        let count = self.matches.len();
+        let (Some(first), Some(last)) = (self.matches.first(), self.matches.last()) else {
+            return 0;
+        };
+        let (first_row, last_row) = (first.start.0, last.end.0);
+        let mut lines = textarea.lines()[first_row..=last_row].to_vec();
+        // Replace from the end, so earlier columns stay valid.
+        for m in self.matches.clone().iter().rev() {
+            let line = &lines[m.start.0 - first_row];
+            let replacement = self.replacement(m, line);
+            let byte = |col: usize| line.char_indices().nth(col).map_or(line.len(), |(b, _)| b);
+            let (start, end) = (byte(m.start.1), byte(m.end.1));
+            let length = replacement.chars().count();
+            lines[m.start.0 - first_row].replace_range(start..end, &replacement);
+            self.shift_scope(m, length);
+        }
+
+        textarea.cancel_selection();
+        textarea.move_cursor(CursorMove::Jump(first_row as u16, 0));
+        textarea.start_selection();
+        let end_col = textarea.lines()[last_row].chars().count();
+        textarea.move_cursor(CursorMove::Jump(last_row as u16, end_col as u16));
+        textarea.insert_str(lines.join("\n"));
+        self.origin = (first_row, 0);
+        self.update(textarea);
+        count
+    }
+
+    /// Moves the end of the scope after `m` was replaced by `length` characters.
+    fn shift_scope(&mut self, m: &Match, length: usize) {
+        if let Some(scope) = &mut self.scope {
+            if scope.end.0 == m.end.0 && scope.end.1 >= m.end.1 {
+                scope.end.1 = scope.end.1 + length - (m.end.1 - m.start.1);
+            }
+        }
+    }
+
+    /// Match counter, such as `2/5`.
+    pub fn counter(&self) -> String {
+        match (self.current, self.matches.len()) {
+            (_, 0) => "no matches".to_string(),
+            (Some(current), count) => format!("{}/{count}", current + 1),
+            (None, count) => format!("{count} matches"),
+        }
+    }
+}
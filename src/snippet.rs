@@ -0,0 +1,319 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use regex::{Captures, Regex};
+use tui_textarea::{CursorMove, TextArea};
+
+use crate::front_matter;
+
+/// Snippets that are available without a snippet directory.
+const BUILTIN: &[(&str, &str, &str)] = &[
+    (
+        "csv",
+        "Read a CSV file",
+        "SELECT *\nFROM read_csv('${1:${FILE}}', header = ${2:true})$0",
+    ),
+    (
+        "parquet",
+        "Read Parquet files",
+        "SELECT *\nFROM read_parquet('${1:${FILE}}')$0",
+    ),
+    (
+        "copy",
+        "Write query results to a file",
+        "COPY (${1:SELECT * FROM ${TABLE:tbl}}) TO '${2:output.parquet}' (FORMAT ${3:parquet});$0",
+    ),
+    (
+        "pivot",
+        "Turn the values of a column into columns",
+        "PIVOT ${1:${TABLE:tbl}}\nON ${2:category}\nUSING ${3:sum}(${4:amount})\nGROUP BY ${5:id};$0",
+    ),
+    (
+        "unpivot",
+        "Turn columns into name and value rows",
+        "UNPIVOT ${1:${TABLE:tbl}}\nON ${2:COLUMNS(* EXCLUDE (id))}\nINTO NAME ${3:name} VALUE ${4:value};$0",
+    ),
+    (
+        "window",
+        "Window function over partitions",
+        "${1:row_number}() OVER (PARTITION BY ${2:id} ORDER BY ${3:ts})$0",
+    ),
+    (
+        "cte",
+        "Query with a common table expression",
+        "WITH ${1:cte} AS (\n    ${2:SELECT * FROM ${TABLE:tbl}}\n)\nSELECT *\nFROM ${3:cte}$0",
+    ),
+];
+
+/// A template inserted by typing its trigger word or picking it from a list.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snippet {
+    pub trigger: String,
+    pub description: String,
+    /// Text with `$1`, `${1:default}` placeholders, the final cursor position
+    /// `$0` and `${NAME}` or `${NAME:default}` variables.
+    pub body: String,
+}
+
+impl Snippet {
+    pub fn new(trigger: &str, description: &str, body: &str) -> Self {
+        Self {
+            trigger: trigger.to_string(),
+            description: description.to_string(),
+            body: body.to_string(),
+        }
+    }
+
+    /// Expands the snippet for insertion on a line indented with `indent`.
+    ///
+    /// `variable` looks up the value of a variable, which is `None` for unknown
+    /// ones. Returns the text and the
+    /// placeholders as character offsets into it, in the order they are visited.
+    pub fn expand(
+        &self,
+        indent: &str,
+        variable: impl Fn(&str) -> Option<String>,
+    ) -> (String, Vec<(usize, usize)>) {
+        let body = self.body.replace('\n', &format!("\n{indent}"));
+        // Values are escaped, so a `$1` in them is not taken for a placeholder.
+        let variables = Regex::new(r"\\\$|\$\{([A-Z_][A-Z0-9_]*)(?::([^{}\n]*))?\}").unwrap();
+        let body = variables.replace_all(&body, |captures: &Captures| {
+            // Unknown variables are left for the query to substitute.
+            let Some(value) = captures.get(1).and_then(|name| variable(name.as_str())) else {
+                return captures[0].to_string();
+            };
+            match captures.get(2) {
+                Some(default) if value.is_empty() => default.as_str().to_string(),
+                _ => value.replace('$', r"\$"),
+            }
+        });
+
+        let placeholders = Regex::new(r"\\\$|\$(\d+)|\$\{(\d+)(?::([^{}\n]*))?\}").unwrap();
+        let mut text = String::new();
+        let mut stops: Vec<(usize, usize, usize)> = Vec::new();
+        let mut last = 0;
+        for captures in placeholders.captures_iter(&body) {
+            let whole = captures.get(0).unwrap();
+            text.push_str(&body[last..whole.start()]);
+            last = whole.end();
+            let Some(number) = captures.get(1).or(captures.get(2)) else {
+                text.push('$');
+                continue;
+            };
+            let number: usize = number.as_str().parse().unwrap_or(usize::MAX);
+            let start = text.chars().count();
+            if let Some(default) = captures.get(3) {
+                text.push_str(&default.as_str().replace(r"\$", "$"));
+            }
+            // Only the first of repeated placeholders is visited.
+            if !stops.iter().any(|(n, _, _)| *n == number) {
+                stops.push((number, start, text.chars().count()));
+            }
+        }
+        text.push_str(&body[last..]);
+
+        // `$0` is visited last, or the cursor ends up after the snippet.
+        stops.sort_by_key(|(number, _, _)| if *number == 0 { usize::MAX } else { *number });
+        if stops.last().is_none_or(|(number, _, _)| *number != 0) {
+            let end = text.chars().count();
+            stops.push((0, end, end));
+        }
+        let stops = stops
+            .into_iter()
+            .map(|(_, start, end)| (start, end))
+            .collect();
+        (text, stops)
+    }
+}
+
+/// Loads the built-in snippets and the `.sql` files in `dir`.
+///
+/// A file's name is its trigger, and a `-- description:` line at the top
+/// describes it. Files replace built-in snippets with the same trigger.
+pub fn load(dir: &Path) -> (Vec<Snippet>, Vec<String>) {
+    let mut snippets: Vec<Snippet> = BUILTIN
+        .iter()
+        .map(|(trigger, description, body)| Snippet::new(trigger, description, body))
+        .collect();
+    let mut warnings = Vec::new();
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return (snippets, warnings),
+        Err(e) => return (snippets, vec![format!("{}: {e}", dir.display())]),
+    };
+    let mut paths: Vec<_> = entries
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .filter(|path| path.extension().is_some_and(|extension| extension == "sql"))
+        .collect();
+    paths.sort();
+
+    for path in paths {
+        let Some(trigger) = path.file_stem().and_then(|stem| stem.to_str()) else {
+            continue;
+        };
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) => {
+                warnings.push(format!("{}: {e}", path.display()));
+                continue;
+            }
+        };
+        let (fields, body) = front_matter::parse(&content);
+        let description = fields
+            .iter()
+            .find(|(key, _)| key == "description")
+            .map_or("", |(_, value)| value.as_str());
+        let snippet = Snippet::new(trigger, description, body.trim_end());
+        match snippets.iter_mut().find(|s| s.trigger == snippet.trigger) {
+            Some(existing) => *existing = snippet,
+            None => snippets.push(snippet),
+        }
+    }
+    snippets.sort_by(|a, b| a.trigger.cmp(&b.trigger));
+    (snippets, warnings)
+}
+
+/// A placeholder of an inserted snippet, within one line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Stop {
+    row: usize,
+    start: usize,
+    end: usize,
+}
+
+/// Cursor and line lengths before an edit, to follow it with the placeholders.
+#[derive(Debug, Clone, Copy)]
+pub struct Snapshot {
+    lines: usize,
+    row: usize,
+    length: usize,
+    /// Column where the edit starts: the cursor, or the start of the selection.
+    col: usize,
+}
+
+impl Snapshot {
+    pub fn of(textarea: &TextArea) -> Self {
+        let (row, col) = textarea
+            .selection_range()
+            .map_or(textarea.cursor(), |(start, _)| start);
+        Self {
+            lines: textarea.lines().len(),
+            row,
+            length: textarea.lines()[row].chars().count(),
+            col,
+        }
+    }
+}
+
+/// Placeholders of the snippet being filled in, visited with Tab.
+#[derive(Debug, Clone)]
+pub struct Session {
+    stops: Vec<Stop>,
+    current: usize,
+}
+
+impl Session {
+    /// Inserts `snippet` at the cursor, replacing the selection, and selects
+    /// its first placeholder.
+    ///
+    /// Returns `None` when the snippet has no placeholders besides its end.
+    pub fn insert(
+        snippet: &Snippet,
+        textarea: &mut TextArea<'static>,
+        variable: impl Fn(&str) -> Option<String>,
+    ) -> Option<Self> {
+        let start = textarea
+            .selection_range()
+            .map_or(textarea.cursor(), |(start, _)| start);
+        let indent: String = textarea.lines()[start.0]
+            .chars()
+            .take_while(|c| c.is_whitespace())
+            .collect();
+        let (text, stops) = snippet.expand(&indent, variable);
+        textarea.insert_str(&text);
+
+        let position = |offset: usize| {
+            let before: String = text.chars().take(offset).collect();
+            match before.rsplit_once('\n') {
+                Some((lines, last)) => (
+                    start.0 + lines.matches('\n').count() + 1,
+                    last.chars().count(),
+                ),
+                None => (start.0, start.1 + before.chars().count()),
+            }
+        };
+        let stops: Vec<Stop> = stops
+            .into_iter()
+            .map(|(from, to)| {
+                let (row, start) = position(from);
+                Stop {
+                    row,
+                    start,
+                    end: start + (to - from),
+                }
+            })
+            .collect();
+        let session = Self { stops, current: 0 };
+        session.select(textarea);
+        (session.stops.len() > 1).then_some(session)
+    }
+
+    /// Selects the current placeholder, or moves to it if it is empty.
+    fn select(&self, textarea: &mut TextArea<'static>) {
+        let stop = self.stops[self.current];
+        textarea.cancel_selection();
+        textarea.move_cursor(CursorMove::Jump(stop.row as u16, stop.start as u16));
+        if stop.end > stop.start {
+            textarea.start_selection();
+            textarea.move_cursor(CursorMove::Jump(stop.row as u16, stop.end as u16));
+        }
+    }
+
+    /// Moves to the next or previous placeholder.
+    ///
+    /// Returns false when the end of the snippet is reached, which ends the session.
+    pub fn step(&mut self, forward: bool, textarea: &mut TextArea<'static>) -> bool {
+        self.current = if forward {
+            (self.current + 1).min(self.stops.len() - 1)
+        } else {
+            self.current.saturating_sub(1)
+        };
+        self.select(textarea);
+        self.current + 1 < self.stops.len()
+    }
+
+    /// Moves the placeholders along with an edit made since `before`.
+    ///
+    /// Returns false when the session ends, because lines were added or
+    /// removed or the cursor left the snippet.
+    pub fn follow(&mut self, before: Snapshot, textarea: &TextArea) -> bool {
+        let lines = textarea.lines();
+        if lines.len() != before.lines {
+            return false;
+        }
+        let (row, col) = textarea.cursor();
+        let delta = lines[before.row].chars().count() as isize - before.length as isize;
+        if delta != 0 {
+            let point = if row == before.row {
+                before.col.min(col)
+            } else {
+                before.col
+            };
+            // Text typed at the end of a placeholder extends it, while deleted
+            // text only shortens the placeholders it was part of.
+            let moves = |col: usize| col > point || (delta > 0 && col == point);
+            for stop in self.stops.iter_mut().filter(|stop| stop.row == before.row) {
+                if stop.start > point {
+                    stop.start = stop.start.saturating_add_signed(delta).max(point);
+                }
+                if moves(stop.end) {
+                    stop.end = stop.end.saturating_add_signed(delta).max(point);
+                }
+            }
+        }
+        let first = self.stops.iter().map(|stop| stop.row).min().unwrap_or(0);
+        let last = self.stops.iter().map(|stop| stop.row).max().unwrap_or(0);
+        (first..=last).contains(&row)
+    }
+}
@@ -0,0 +1,50 @@
+/// Base names of the DuckDB number types, shared by the profile, chart and text views.
+const NUMERIC_TYPES: [&str; 12] = [
+    "TINYINT",
+    "SMALLINT",
+    "INTEGER",
+    "BIGINT",
+    "HUGEINT",
+    "UTINYINT",
+    "USMALLINT",
+    "UINTEGER",
+    "UBIGINT",
+    "FLOAT",
+    "DOUBLE",
+    "DECIMAL",
+];
+
+/// Returns the name of a DuckDB type without its parameters, such as
+/// `DECIMAL` for `DECIMAL(18,3)`.
+///
+/// List and array types keep their suffix, so `DECIMAL(18,3)[]` and
+/// `INTEGER[3]` are returned whole, and nested types return `STRUCT`, `MAP`
+/// or `UNION`.
+pub fn base_type(data_type: &str) -> &str {
+    let data_type = data_type.trim();
+    data_type
+        .strip_suffix(')')
+        .and_then(|rest| rest.split_once('('))
+        .map_or(data_type, |(base, _)| base.trim())
+}
+
+/// Whether values of the DuckDB type `data_type` are numbers, not lists or
+/// structs of numbers.
+pub fn is_numeric(data_type: &str) -> bool {
+    NUMERIC_TYPES.contains(&base_type(data_type))
+}
+
+/// Builds a query splitting the non-null values of `expr` over `source` into
+/// `bins` equal ranges.
+///
+/// Returns one row per non-empty bin with its index `bin`, its count `n`, and
+/// the overall minimum `lo` and maximum `hi`.
+pub fn histogram_query(expr: &str, source: &str, bins: usize) -> String {
+    format!(
+        "SELECT bin, count(*) AS n, min(lo) AS lo, min(hi) AS hi FROM (\
+         SELECT least(floor((v - lo) / coalesce(nullif(hi - lo, 0), 1) * {bins}), {last})::INTEGER AS bin, lo, hi \
+         FROM (SELECT v, min(v) OVER () AS lo, max(v) OVER () AS hi FROM (SELECT {expr} AS v FROM {source}) WHERE v IS NOT NULL)) \
+         GROUP BY bin ORDER BY bin",
+        last = bins - 1
+    )
+}
@@ -0,0 +1,251 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::Style,
+    widgets::{Block, StatefulWidget, Widget},
+};
+use unicode_width::UnicodeWidthStr;
+
+/// A node of a collapsible tree.
+///
+/// Each node is rendered on its own line as label, detail and value.
+#[derive(Debug, Clone, Default)]
+pub struct TreeNode {
+    pub label: String,
+    pub detail: String,
+    pub value: String,
+    pub style: Style,
+    pub expanded: bool,
+    pub children: Vec<TreeNode>,
+}
+
+impl TreeNode {
+    pub fn new(label: impl Into<String>, value: impl Into<String>) -> Self {
+        Self {
+            label: label.into(),
+            value: value.into(),
+            ..Default::default()
+        }
+    }
+
+    pub fn detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail = detail.into();
+        self
+    }
+
+    pub fn style(mut self, style: Style) -> Self {
+        self.style = style;
+        self
+    }
+
+    pub fn expanded(mut self, expanded: bool) -> Self {
+        self.expanded = expanded;
+        self
+    }
+
+    pub fn children(mut self, children: Vec<TreeNode>) -> Self {
+        self.children = children;
+        self
+    }
+}
+
+/// Returns the visible nodes in display order together with their depth.
+pub fn flatten(nodes: &[TreeNode]) -> Vec<(usize, &TreeNode)> {
+    fn walk<'a>(nodes: &'a [TreeNode], depth: usize, out: &mut Vec<(usize, &'a TreeNode)>) {
+        for node in nodes {
+            out.push((depth, node));
+            if node.expanded {
+                walk(&node.children, depth + 1, out);
+            }
+        }
+    }
+    let mut out = Vec::new();
+    walk(nodes, 0, &mut out);
+    out
+}
+
+/// Expands or collapses the visible node at `index`.
+pub fn toggle(nodes: &mut [TreeNode], index: usize) {
+    fn walk(nodes: &mut [TreeNode], index: &mut usize) -> bool {
+        for node in nodes {
+            if *index == 0 {
+                if !node.children.is_empty() {
+                    node.expanded = !node.expanded;
+                }
+                return true;
+            }
+            *index -= 1;
+            if node.expanded && walk(&mut node.children, index) {
+                return true;
+            }
+        }
+        false
+    }
+    let mut index = index;
+    walk(nodes, &mut index);
+}
+
+/// Returns the position of the top-level node containing the visible node at `index`.
+pub fn root_of(nodes: &[TreeNode], index: usize) -> Option<usize> {
+    let mut remaining = index;
+    for (i, node) in nodes.iter().enumerate() {
+        let size = if node.expanded {
+            1 + flatten(&node.children).len()
+        } else {
+            1
+        };
+        if remaining < size {
+            return Some(i);
+        }
+        remaining -= size;
+    }
+    None
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TreeState {
+    pub offset: usize,
+    pub selected: usize,
+}
+
+impl TreeState {
+    pub fn select(&mut self, amount: isize) {
+        self.selected = self.selected.saturating_add_signed(amount);
+    }
+}
+
+/// Widget rendering a slice of [`TreeNode`]s.
+pub struct Tree<'a> {
+    nodes: &'a [TreeNode],
+    block: Option<Block<'static>>,
+    style: Style,
+    label_style: Style,
+    detail_style: Style,
+    highlight_style: Style,
+}
+
+impl<'a> Tree<'a> {
+    pub fn new(nodes: &'a [TreeNode]) -> Self {
+        Self {
+            nodes,
+            block: None,
+            style: Style::default(),
+            label_style: Style::default(),
+            detail_style: Style::default(),
+            highlight_style: Style::default(),
+        }
+    }
+
+    pub fn block(mut self, block: Block<'static>) -> Self {
+        self.block = Some(block);
+        self
+    }
+
+    pub fn style(mut self, style: Style) -> Self {
+        self.style = style;
+        self
+    }
+
+    pub fn label_style(mut self, style: Style) -> Self {
+        self.label_style = style;
+        self
+    }
+
+    pub fn detail_style(mut self, style: Style) -> Self {
+        self.detail_style = style;
+        self
+    }
+
+    pub fn highlight_style(mut self, style: Style) -> Self {
+        self.highlight_style = style;
+        self
+    }
+}
+
+impl StatefulWidget for Tree<'_> {
+    type State = TreeState;
+
+    fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+        let area = match self.block {
+            Some(b) => {
+                let inner_area = b.inner(area);
+                b.render(area, buf);
+                inner_area
+            }
+            None => area,
+        };
+
+        if area.width < 1 || area.height < 1 {
+            return;
+        }
+
+        buf.set_style(area, self.style);
+
+        let rows = flatten(self.nodes);
+        if rows.is_empty() {
+            return;
+        }
+
+        let height = area.height as usize;
+        state.selected = state.selected.min(rows.len() - 1);
+        if state.selected < state.offset {
+            state.offset = state.selected;
+        } else if state.selected >= state.offset + height {
+            state.offset = state.selected + 1 - height;
+        }
+
+        let label_width = rows
+            .iter()
+            .map(|(depth, node)| depth * 2 + 2 + node.label.width())
+            .max()
+            .unwrap_or(0)
+            .min(area.width as usize / 3);
+        let detail_width = rows
+            .iter()
+            .map(|(_, node)| node.detail.width())
+            .max()
+            .unwrap_or(0)
+            .min(area.width as usize / 4);
+        let detail_x = area.left() as usize + label_width + 1;
+        let value_x = detail_x + detail_width + usize::from(detail_width > 0);
+        let value_width = (area.right() as usize).saturating_sub(value_x);
+
+        for (y, (index, (depth, node))) in
+            (area.top()..area.bottom()).zip(rows.iter().enumerate().skip(state.offset))
+        {
+            let marker = match (node.children.is_empty(), node.expanded) {
+                (true, _) => "  ",
+                (false, true) => "▾ ",
+                (false, false) => "▸ ",
+            };
+            let label = format!("{}{}{}", "  ".repeat(*depth), marker, node.label);
+            buf.set_stringn(
+                area.left(),
+                y,
+                label,
+                label_width,
+                self.label_style.patch(node.style),
+            );
+            buf.set_stringn(
+                detail_x as u16,
+                y,
+                &node.detail,
+                detail_width,
+                self.detail_style,
+            );
+            buf.set_stringn(
+                value_x as u16,
+                y,
+                &node.value,
+                value_width,
+                self.style.patch(node.style),
+            );
+            if index == state.selected {
+                buf.set_style(
+                    Rect::new(area.left(), y, area.width, 1),
+                    self.highlight_style,
+                );
+            }
+        }
+    }
+}
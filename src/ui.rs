@@ -1,23 +1,31 @@
+use crate::app::App;
+use crate::chart::ChartView;
+use crate::diff::{Change, DiffView};
+use crate::editing;
+use crate::notify::Level;
+use crate::output::{self, TextView};
+use crate::profile::ProfileView;
+use crate::search::SearchField;
+use crate::tree::Tree;
+use ratatui::widgets::block::Block;
 use ratatui::{
     layout::{Alignment, Constraint, Flex, Layout, Rect},
-    style::{Color, Style, Stylize},
-    text::Text,
-    widgets::{BorderType, Borders, Clear, Paragraph, Wrap},
+    style::{Color, Modifier, Style, Stylize},
+    text::{Line, Span},
+    widgets::{BorderType, Borders, Clear, Paragraph, Tabs, Wrap},
     Frame,
 };
 use ratatui_explorer::Theme;
-use ratatui::widgets::block::Block;
-use crate::app::App;
 
-const BG: Color = Color::Rgb(40, 40, 40); // Dark background
-const FG: Color = Color::Rgb(235, 219, 178); // Light foreground
-const RED: Color = Color::Rgb(204, 36, 29); // Gruvbox red
-const GREEN: Color = Color::Rgb(152, 151, 26); // Gruvbox green
-const YELLOW: Color = Color::Rgb(215, 153, 33); // Gruvbox yellow
-const BLUE: Color = Color::Rgb(69, 133, 136); // Gruvbox blue
-const PURPLE: Color = Color::Rgb(177, 98, 134); // Gruvbox purple
-const AQUA: Color = Color::Rgb(104, 157, 106); // Gruvbox aqua
-const GRAY: Color = Color::Rgb(146, 131, 116); // Gruvbox gray
+pub const BG: Color = Color::Rgb(40, 40, 40); // Dark background
+pub const FG: Color = Color::Rgb(235, 219, 178); // Light foreground
+pub const RED: Color = Color::Rgb(204, 36, 29); // Gruvbox red
+pub const GREEN: Color = Color::Rgb(152, 151, 26); // Gruvbox green
+pub const YELLOW: Color = Color::Rgb(215, 153, 33); // Gruvbox yellow
+pub const BLUE: Color = Color::Rgb(69, 133, 136); // Gruvbox blue
+pub const PURPLE: Color = Color::Rgb(177, 98, 134); // Gruvbox purple
+pub const AQUA: Color = Color::Rgb(104, 157, 106); // Gruvbox aqua
+pub const GRAY: Color = Color::Rgb(146, 131, 116); // Gruvbox gray
 
 fn popup_area(area: Rect, percent_x: u16, percent_y: u16) -> Rect {
     let vertical = Layout::vertical([Constraint::Percentage(percent_y)]).flex(Flex::Center);
@@ -27,17 +35,436 @@ fn popup_area(area: Rect, percent_x: u16, percent_y: u16) -> Rect {
     area
 }
 
+/// Builds the status bar from the application state.
+fn status_line(app: &App) -> Line<'static> {
+    let segment =
+        |text: String, fg: Color| Span::styled(format!(" {text} "), Style::default().fg(fg));
+    let separator = || Span::styled("│", Style::default().fg(GRAY));
+
+    let tab = app.tab();
+    let last_query = if tab.query.is_empty() {
+        "no query yet".to_string()
+    } else {
+        format!(
+            "{} rows × {} cols in {} ms",
+            tab.table.rows.len(),
+            tab.table.headers.len(),
+            tab.elapsed.as_millis()
+        )
+    };
+    let database = app
+        .database
+        .as_ref()
+        .map_or(":memory:".to_string(), |path| path.display().to_string());
+    let transaction = if app.in_transaction {
+        segment("transaction open".to_string(), YELLOW)
+    } else {
+        segment("autocommit".to_string(), GRAY)
+    };
+
+    let mut spans = vec![
+        Span::styled(" Quackmire ", Style::default().fg(BG).bg(AQUA).bold()),
+        segment(app.focus().to_string(), PURPLE),
+    ];
+    if let Some(validation) = &app.validation {
+        let diagnostic = &validation.diagnostic;
+        spans.push(Span::styled(
+            format!(" ✗ {}: {} ", diagnostic.kind, diagnostic.message),
+            Style::default().fg(BG).bg(RED).bold(),
+        ));
+    }
+    let mode = app.editor_mode.status();
+    if !mode.is_empty() {
+        spans.push(Span::styled(
+            format!(" {mode} "),
+            Style::default().fg(BG).bg(YELLOW).bold(),
+        ));
+    }
+    spans.extend([
+        separator(),
+        segment(database, BLUE),
+        separator(),
+        segment(app.file_explorer.cwd().display().to_string(), FG),
+        separator(),
+        segment(last_query, GREEN),
+        separator(),
+        transaction,
+    ]);
+    if let Some(output) = &app.output {
+        spans.push(separator());
+        spans.push(segment(format!("→ {}", output.display()), AQUA));
+    }
+    if let Some(job) = &app.job {
+        spans.push(separator());
+        spans.push(segment(format!("⟳ {}", job.description()), YELLOW));
+    }
+    spans.extend([separator(), segment("F1 help".to_string(), GRAY)]);
+    Line::from(spans)
+}
+
+fn level_color(level: Level) -> Color {
+    match level {
+        Level::Info => BLUE,
+        Level::Success => GREEN,
+        Level::Warning => YELLOW,
+        Level::Error => RED,
+    }
+}
+
+/// Renders the find and replace bar under the editor.
+fn render_search(app: &App, frame: &mut Frame, area: Rect) {
+    let search = &app.search;
+    let flag = |on: bool, label: &'static str| {
+        let style = if on {
+            Style::default().fg(BG).bg(AQUA).bold()
+        } else {
+            Style::default().fg(GRAY)
+        };
+        Span::styled(format!(" {label} "), style)
+    };
+    let field = |label: &'static str, text: &str, active: bool| {
+        let style = if active {
+            Style::default().fg(FG).bold()
+        } else {
+            Style::default().fg(GRAY)
+        };
+        let cursor = if active { "▏" } else { "" };
+        Line::from(vec![
+            Span::styled(label, Style::default().fg(AQUA)),
+            Span::styled(format!("{text}{cursor}"), style),
+        ])
+    };
+
+    let status = match &search.error {
+        Some(error) => Span::styled(format!(" {error} "), Style::default().fg(RED)),
+        None => Span::styled(format!(" {} ", search.counter()), Style::default().fg(FG)),
+    };
+    let mut find = field("Find:    ", &search.find, search.field == SearchField::Find);
+    find.spans.extend([
+        Span::raw("  "),
+        flag(search.regex, ".*"),
+        flag(search.case_sensitive, "Aa"),
+        status,
+    ]);
+    let replace = field(
+        "Replace: ",
+        &search.replace,
+        search.field == SearchField::Replace,
+    );
+
+    let title = if search.scope.is_some() {
+        "Find in Selection"
+    } else {
+        "Find"
+    };
+    let block = Block::bordered()
+        .title(title)
+        .title_bottom(
+            Line::from(
+                " Enter next/replace │ ↑↓ move │ Tab switch │ Alt+r regex │ Alt+c case │ Alt+a replace all │ Esc close ",
+            )
+            .right_aligned(),
+        )
+        .border_type(BorderType::Rounded)
+        .style(Style::default().fg(AQUA).bg(BG));
+    frame.render_widget(Paragraph::new(vec![find, replace]).block(block), area);
+}
+
+/// Part of the editor shown on screen.
+#[derive(Clone, Copy)]
+struct EditorView {
+    /// Text area inside the border.
+    area: Rect,
+    /// Width of the line number gutter.
+    gutter: usize,
+    /// First row shown.
+    top: usize,
+    /// First column shown, counting the gutter.
+    left: usize,
+}
+
+/// Part of the editor shown in `area`, from the scroll position kept by its buffer.
+fn editor_view(app: &App, area: Rect) -> Option<EditorView> {
+    let buffer = app.buffer();
+    let textarea = &buffer.textarea;
+    let area = textarea.block().map_or(area, |block| block.inner(area));
+    if area.width == 0 || area.height == 0 {
+        return None;
+    }
+    Some(EditorView {
+        area,
+        gutter: textarea.lines().len().to_string().len() + 2,
+        top: buffer.scroll.0 as usize,
+        left: buffer.scroll.1 as usize,
+    })
+}
+
+/// Screen row of the editor's `row`, if it is shown.
+fn editor_y(view: EditorView, row: usize) -> Option<u16> {
+    row.checked_sub(view.top)
+        .filter(|&row| row < view.area.height as usize)
+        .map(|row| view.area.y + row as u16)
+}
+
+/// Screen column of the editor's `column`, counting the gutter, if it is shown.
+fn editor_x(view: EditorView, column: usize) -> Option<u16> {
+    column
+        .checked_sub(view.left)
+        .filter(|&column| column < view.area.width as usize)
+        .map(|column| view.area.x + column as u16)
+}
+
+/// Column of the character at `col` of `row` in the editor, counting the gutter.
+fn editor_column(app: &App, view: EditorView, (row, col): (usize, usize)) -> Option<usize> {
+    let textarea = &app.buffer().textarea;
+    let line = textarea.lines().get(row)?;
+    Some(view.gutter + display_width(line.chars().take(col), textarea.tab_length()))
+}
+
+/// Marks the problem found while typing in the gutter and under its position.
+fn render_validation(app: &App, frame: &mut Frame, area: Rect) {
+    let Some(validation) = &app.validation else {
+        return;
+    };
+    let Some(view) = editor_view(app, area) else {
+        return;
+    };
+    let Some(y) = editor_y(view, validation.row) else {
+        return;
+    };
+
+    let buf = frame.buffer_mut();
+    for column in 0..view.gutter {
+        if let Some(x) = editor_x(view, column) {
+            if column == 0 {
+                buf[(x, y)].set_symbol("●");
+            }
+            buf[(x, y)].set_fg(RED);
+        }
+    }
+    let diagnostic = &validation.diagnostic;
+    let Some((row, col)) = diagnostic.position else {
+        return;
+    };
+    let Some(y) = editor_y(view, row) else {
+        return;
+    };
+    let length = diagnostic
+        .token
+        .as_ref()
+        .map_or(1, |token| token.chars().count());
+    let (Some(start), Some(end)) = (
+        editor_column(app, view, (row, col)),
+        editor_column(app, view, (row, col + length)),
+    ) else {
+        return;
+    };
+    for column in start..end.max(start + 1) {
+        if let Some(x) = editor_x(view, column) {
+            buf[(x, y)].set_style(Style::default().fg(RED).add_modifier(Modifier::UNDERLINED));
+        }
+    }
+}
+
+/// Highlights the bracket at the cursor and the one matching it.
+fn render_brackets(app: &App, frame: &mut Frame, area: Rect) {
+    let textarea = &app.buffer().textarea;
+    let Some(pair) = editing::matching_bracket(textarea.lines(), textarea.cursor()) else {
+        return;
+    };
+    let Some(view) = editor_view(app, area) else {
+        return;
+    };
+    let buf = frame.buffer_mut();
+    for position in [pair.0, pair.1] {
+        let cell = editor_column(app, view, position)
+            .and_then(|column| editor_x(view, column))
+            .zip(editor_y(view, position.0));
+        if let Some(cell) = cell {
+            buf[cell].set_style(Style::default().fg(YELLOW).add_modifier(Modifier::BOLD));
+        }
+    }
+}
+
+/// Width of `text` in the editor, where tabs reach the next tab stop.
+fn display_width(text: impl Iterator<Item = char>, tab_length: u8) -> usize {
+    let tab_length = tab_length as usize;
+    text.fold(0, |width, c| match c {
+        '\t' if tab_length > 0 => width + tab_length - width % tab_length,
+        '\t' => width,
+        c => width + unicode_width::UnicodeWidthChar::width(c).unwrap_or(0),
+    })
+}
+
+/// Key bindings shown by the help popup.
+const KEY_BINDINGS: &[(&str, &str)] = &[
+    ("F1", "Show or hide this help"),
+    ("F2", "Run the query"),
+    ("F3", "Record view of the selected row"),
+    ("F4", "Inspect the selected cell"),
+    ("F5", "Unnest the selected column"),
+    ("F6", "Profile the results"),
+    ("F7", "Chart the results"),
+    ("F8", "Compare with another tab or query"),
+    ("F9", "Profile the statement and show its plan"),
+    ("F10", "Message log"),
+    ("Super + Arrows", "Navigate the file explorer"),
+    ("Ctrl + a", "Insert the selected file path into the query"),
+    ("Alt + Arrows", "Move through the results"),
+    ("Alt + 1-9", "Switch result tab"),
+    (
+        "Alt + t / Alt + w",
+        "Store the tab as a temp table, or close it",
+    ),
+    ("Ctrl + o", "Open a file"),
+    ("Ctrl + t / Ctrl + q", "New or close buffer"),
+    ("Ctrl + PgUp / PgDn", "Switch buffer"),
+    ("Ctrl + s / Alt + s", "Save, or save as"),
+    ("Ctrl + f", "Find and replace"),
+    ("Alt + e", "Cycle standard, vim and emacs keys"),
+    ("Alt + ;", "Comment lines"),
+    ("Alt + n", "Snippets"),
+    ("Alt + l / Alt + b", "Browse or save to the query library"),
+    ("Alt + v", "Preview variables"),
+    ("Alt + m / Alt + i", "Result mode, column types"),
+    ("Alt + q", "Format the query"),
+    ("Alt + o", "Edit in $EDITOR"),
+    ("Esc", "Close a popup, or exit"),
+];
+
+/// Renders the key bindings in a popup.
+fn render_help(frame: &mut Frame) {
+    let area = popup_area(frame.area(), 60, 100);
+    let height = (KEY_BINDINGS.len() as u16 + 2).min(area.height);
+    let popup_area = Rect {
+        y: area.y + (area.height - height) / 2,
+        height,
+        ..area
+    };
+    frame.render_widget(Clear, popup_area);
+    let width = KEY_BINDINGS
+        .iter()
+        .map(|(keys, _)| keys.chars().count())
+        .max()
+        .unwrap_or_default();
+    let lines: Vec<Line> = KEY_BINDINGS
+        .iter()
+        .map(|(keys, description)| {
+            Line::from(vec![
+                Span::styled(format!(" {keys:<width$}  "), Style::default().fg(YELLOW)),
+                Span::raw(*description),
+            ])
+        })
+        .collect();
+    let help = Paragraph::new(lines)
+        .style(Style::default().fg(FG).bg(BG))
+        .block(
+            Block::bordered()
+                .title("Key bindings")
+                .title_bottom("F1 or Esc to close")
+                .border_type(BorderType::Rounded)
+                .border_style(Style::default().fg(AQUA).bg(BG)),
+        );
+    frame.render_widget(help, popup_area);
+}
+
+/// Renders the message log, scrolled up from its end by `app.log_scroll`.
+fn render_log(app: &App, frame: &mut Frame, area: Rect) {
+    let log = &app.notifications.log;
+    let height = area.height.saturating_sub(2) as usize;
+    let end = log.len().saturating_sub(app.log_scroll);
+    let lines: Vec<Line> = log[end.saturating_sub(height)..end]
+        .iter()
+        .map(|notice| {
+            let mut spans = vec![
+                Span::styled(format!("{} ", notice.time), Style::default().fg(GRAY)),
+                Span::styled(
+                    format!("{:<6}", notice.level.name()),
+                    Style::default().fg(level_color(notice.level)).bold(),
+                ),
+                Span::raw(notice.message.replace('\n', " ")),
+            ];
+            if let Some(statement) = &notice.statement {
+                spans.push(Span::styled(
+                    format!(
+                        "  ⟵ {}",
+                        statement.split_whitespace().collect::<Vec<_>>().join(" ")
+                    ),
+                    Style::default().fg(GRAY),
+                ));
+            }
+            Line::from(spans)
+        })
+        .collect();
+    let title = format!("Messages ({}, Alt + PgUp/PgDn to scroll)", log.len());
+    let paragraph = Paragraph::new(lines)
+        .block(
+            Block::bordered()
+                .title(title)
+                .title_alignment(Alignment::Left)
+                .border_type(BorderType::Rounded)
+                .border_style(Style::default().fg(AQUA).bg(BG)),
+        )
+        .style(Style::default().fg(FG).bg(BG));
+    frame.render_widget(paragraph, area);
+}
+
+/// Renders the visible toasts stacked in the top right corner.
+fn render_toasts(app: &App, frame: &mut Frame) {
+    let area = frame.area();
+    let mut y = area.top() + 1;
+    for notice in app.notifications.toasts() {
+        let text = format!(" {} ", notice.message.replace('\n', " "));
+        let width = (text.chars().count() as u16 + 2).min(area.width / 2);
+        if width < 3 || y + 3 > area.bottom() {
+            break;
+        }
+        let toast_area = Rect::new(area.right().saturating_sub(width + 1), y, width, 3);
+        let color = level_color(notice.level);
+        frame.render_widget(Clear, toast_area);
+        frame.render_widget(
+            Paragraph::new(text)
+                .block(
+                    Block::bordered()
+                        .title(notice.level.name())
+                        .border_type(BorderType::Rounded)
+                        .border_style(Style::default().fg(color).bg(BG)),
+                )
+                .style(Style::default().fg(FG).bg(BG)),
+            toast_area,
+        );
+        y += 3;
+    }
+}
+
 /// Renders the user interface widgets.
 pub fn render(app: &mut App, frame: &mut Frame) {
-    app.textarea.set_block(
+    let mut title = match &app.buffer().path {
+        Some(path) => format!("Query: {}", path.display()),
+        None => "Query".to_string(),
+    };
+    if app.buffer().dirty {
+        title.push_str(" [modified]");
+    }
+    let error_underline = app.error_underline;
+    let textarea = &mut app.buffer_mut().textarea;
+    textarea.set_block(
         Block::bordered()
-            .title("Query")
+            .title(title)
             .title_alignment(Alignment::Left)
             .border_type(BorderType::Rounded)
             .style(Style::default().fg(AQUA).bg(BG)),
     );
-    app.textarea.set_line_number_style(Style::default().fg(FG));
-    app.textarea.set_style(Style::default().fg(FG));
+    textarea.set_line_number_style(Style::default().fg(FG));
+    textarea.set_selection_style(if error_underline {
+        Style::default()
+            .fg(RED)
+            .add_modifier(Modifier::UNDERLINED | Modifier::BOLD)
+    } else {
+        Style::default().bg(Color::LightBlue)
+    });
+    textarea.set_search_style(Style::default().fg(BG).bg(YELLOW));
+    textarea.set_style(Style::default().fg(FG));
 
     let theme = Theme::default()
         .add_default_title()
@@ -50,30 +477,163 @@ pub fn render(app: &mut App, frame: &mut Frame) {
         .with_dir_style(Style::default().fg(AQUA));
     app.file_explorer.set_theme(theme);
 
-    let layout = Layout::vertical([
-        Constraint::Length(3),
-        Constraint::Min(1),
-        Constraint::Length(3),
-    ]);
-    let [top, middle, bottom] = layout.areas(frame.area());
+    let layout = Layout::vertical([Constraint::Min(1), Constraint::Length(1)]);
+    let [middle, status] = layout.areas(frame.area());
     let horizontal = Layout::horizontal([Constraint::Percentage(25), Constraint::Percentage(75)]);
     let vertical = Layout::vertical([Constraint::Percentage(50), Constraint::Percentage(50)]);
+    let [middle, log_area] = if app.show_log {
+        Layout::vertical([Constraint::Percentage(70), Constraint::Percentage(30)]).areas(middle)
+    } else {
+        [middle, Rect::default()]
+    };
     let [explorer, right] = horizontal.areas(middle);
     let [query, results] = vertical.areas(right);
+    let [buffers_area, query] =
+        Layout::vertical([Constraint::Length(1), Constraint::Min(1)]).areas(query);
+    let [query, search_area] = if app.show_search {
+        Layout::vertical([Constraint::Min(1), Constraint::Length(4)]).areas(query)
+    } else {
+        [query, Rect::default()]
+    };
+    let [results, profile_area] = if app.profile.is_some() {
+        Layout::horizontal([Constraint::Percentage(60), Constraint::Percentage(40)]).areas(results)
+    } else {
+        [results, Rect::default()]
+    };
+    let [tabs_area, results] =
+        Layout::vertical([Constraint::Length(1), Constraint::Min(1)]).areas(results);
+    let active = app.active_tab;
 
-    let header = Paragraph::new("Quackmire")
-        .block(
+    let tabs = Tabs::new(
+        app.tabs
+            .iter()
+            .enumerate()
+            .map(|(i, tab)| format!("{}: {}", i + 1, tab.title())),
+    )
+    .select(active)
+    .style(Style::default().fg(GRAY).bg(BG))
+    .highlight_style(Style::default().fg(AQUA).bold());
+    frame.render_widget(tabs, tabs_area);
+
+    if let Some(plan) = &app.plan {
+        let title = format!("Plan ({:.3} ms, Alt + Enter to fold)", plan.total * 1000.0);
+        let tree = Tree::new(&plan.nodes)
+            .block(
+                Block::bordered()
+                    .title(title)
+                    .title_alignment(Alignment::Left)
+                    .border_type(BorderType::Rounded)
+                    .border_style(Style::default().fg(AQUA).bg(BG)),
+            )
+            .style(Style::default().fg(FG).bg(BG))
+            .label_style(Style::default().fg(PURPLE).bold())
+            .detail_style(Style::default().fg(GRAY))
+            .highlight_style(Style::default().bg(GRAY));
+        frame.render_stateful_widget(tree, results, &mut app.plan_state);
+    } else if let Some(diff) = &app.diff {
+        let title = format!(
+            "Diff: {} vs {} on {} | +{} -{} ~{}",
+            diff.left_name,
+            diff.right_name,
+            diff.key.as_deref().unwrap_or("all columns"),
+            diff.count(Change::Added),
+            diff.count(Change::Removed),
+            diff.count(Change::Changed)
+        );
+        let view = DiffView::new(diff).block(
             Block::bordered()
+                .title(title)
                 .title_alignment(Alignment::Left)
                 .border_type(BorderType::Rounded)
                 .border_style(Style::default().fg(AQUA).bg(BG)),
-        )
-        .style(Style::new().fg(FG).bg(BG));
+        );
+        frame.render_stateful_widget(view, results, &mut app.diff_state);
+    } else if let Some(data) = &app.chart_data {
+        let name = |i: usize| {
+            app.tabs[active]
+                .table
+                .headers
+                .get(i)
+                .cloned()
+                .unwrap_or_default()
+        };
+        let title = format!(
+            "Chart: {} | x: {} | y: {} | column: {} (Alt + k kind, Alt + x/y pick)",
+            app.chart.kind.name(),
+            name(app.chart.x),
+            app.chart
+                .series
+                .iter()
+                .map(|&i| name(i))
+                .collect::<Vec<_>>()
+                .join(", "),
+            name(app.tabs[active].table_state.selected_col)
+        );
+        let chart = ChartView::new(data)
+            .block(
+                Block::bordered()
+                    .title(title)
+                    .title_alignment(Alignment::Left)
+                    .border_type(BorderType::Rounded)
+                    .border_style(Style::default().fg(AQUA).bg(BG)),
+            )
+            .style(Style::default().fg(FG).bg(BG))
+            .axis_style(Style::default().fg(GRAY))
+            .palette(&[YELLOW, AQUA, PURPLE, GREEN, BLUE, RED]);
+        frame.render_widget(chart, results);
+    } else if !app.tabs[active].table.is_empty() && app.show_record_view {
+        let title = format!(
+            "Record {}/{}",
+            app.tabs[active].table_state.selected + 1,
+            app.tabs[active].table.rows.len()
+        );
+        let record = Tree::new(&app.record)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_type(BorderType::Rounded)
+                    .border_style(Style::default().fg(AQUA).bg(BG))
+                    .title(title)
+                    .title_alignment(Alignment::Left),
+            )
+            .style(Style::default().fg(FG).bg(BG))
+            .label_style(Style::default().fg(PURPLE).bold())
+            .detail_style(Style::default().fg(GRAY))
+            .highlight_style(Style::default().bg(GRAY));
 
-    frame.render_widget(header, top);
+        frame.render_stateful_widget(record, results, &mut app.record_state);
+    } else if !app.tabs[active].table.is_empty() && !app.result_mode.is_grid() {
+        let tab = &app.tabs[active];
+        let title = format!(
+            "Results ({} rows in {} ms) | {} (Alt + m mode)",
+            tab.table.rows.len(),
+            tab.elapsed.as_millis(),
+            app.result_mode
+        );
+        let lines = output::lines(&tab.table, app.result_mode);
+        let view = TextView::new(&lines)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_type(BorderType::Rounded)
+                    .border_style(Style::default().fg(AQUA).bg(BG))
+                    .title(title)
+                    .title_alignment(Alignment::Left),
+            )
+            .style(Style::default().fg(FG).bg(BG))
+            .gutter_style(Style::default().fg(GRAY))
+            .selected_style(Style::default().bg(GRAY));
 
-    if !app.table.is_empty() {
-        let table = app
+        frame.render_stateful_widget(view, results, &mut app.tabs[active].table_state);
+    } else if !app.tabs[active].table.is_empty() {
+        let tab = &app.tabs[active];
+        let title = format!(
+            "Results ({} rows in {} ms) | {} (Alt + m mode, Alt + i types)",
+            tab.table.rows.len(),
+            tab.elapsed.as_millis(),
+            app.result_mode
+        );
+        let table = tab
             .table
             .clone()
             .block(
@@ -81,14 +641,18 @@ pub fn render(app: &mut App, frame: &mut Frame) {
                     .borders(Borders::ALL)
                     .border_type(BorderType::Rounded)
                     .border_style(Style::default().fg(AQUA).bg(BG))
-                    .title("Results")
+                    .title(title)
                     .title_alignment(Alignment::Left),
             )
             .style(Style::default().fg(FG).bg(BG))
-            .header_style(Style::default().fg(PURPLE).bold());
-        // .selected_style(Style::default().bg(FG));
+            .header_style(Style::default().fg(PURPLE).bold())
+            .selected_style(Style::default().bg(GRAY))
+            .borders(app.result_mode == output::Mode::Box)
+            .row_numbers(true)
+            .show_types(app.show_types)
+            .muted_style(Style::default().fg(GRAY));
 
-        frame.render_stateful_widget(table, results, &mut app.table_state);
+        frame.render_stateful_widget(table, results, &mut app.tabs[active].table_state);
     } else {
         let no_results = Paragraph::new("")
             .block(
@@ -100,33 +664,186 @@ pub fn render(app: &mut App, frame: &mut Frame) {
             .style(Style::default().fg(AQUA).bg(BG));
         frame.render_widget(no_results, results);
     }
+    if let Some(profile) = &app.profile {
+        let view = ProfileView::new(profile)
+            .block(
+                Block::bordered()
+                    .title(profile.title.clone())
+                    .title_alignment(Alignment::Left)
+                    .border_type(BorderType::Rounded)
+                    .border_style(Style::default().fg(AQUA).bg(BG)),
+            )
+            .style(Style::default().fg(FG).bg(BG))
+            .name_style(Style::default().fg(PURPLE).bold())
+            .label_style(Style::default().fg(GRAY))
+            .chart_style(Style::default().fg(YELLOW));
+        frame.render_stateful_widget(view, profile_area, &mut app.profile_state);
+    }
+
     frame.render_widget_ref(app.file_explorer.widget(), explorer);
 
-    frame.render_widget(app.textarea.widget(), query);
+    let buffers = Tabs::new(app.buffers.iter().map(|buffer| buffer.title()))
+        .select(app.active_buffer)
+        .style(Style::default().fg(GRAY).bg(BG))
+        .highlight_style(Style::default().fg(AQUA).bold());
+    frame.render_widget(buffers, buffers_area);
+    let editor = app
+        .buffer()
+        .textarea
+        .block()
+        .map_or(query, |block| block.inner(query));
+    app.buffer_mut()
+        .scroll_to_cursor(editor.width, editor.height);
+    frame.render_widget(app.buffer().textarea.widget(), query);
+    render_validation(app, frame, query);
+    render_brackets(app, frame, query);
+    if app.show_search {
+        render_search(app, frame, search_area);
+    }
 
-    let footer = Paragraph::new("Super + Arrows to navigate | Fn + 2 for query | ^o to open file | ^s to save file | ^a to enter file path into query | Esc to exit")
-        .block(
-            Block::bordered()
-                .title_alignment(Alignment::Left)
-                .border_type(BorderType::Rounded)
-                .border_style(Style::default().fg(AQUA).bg(BG)),
-        )
-        .style(Style::default().fg(FG).bg(BG));
-    frame.render_widget(footer, bottom);
+    frame.render_widget(
+        Paragraph::new(status_line(app)).style(Style::default().fg(FG).bg(BG)),
+        status,
+    );
+
+    if app.show_log {
+        render_log(app, frame, log_area);
+    }
+
+    render_toasts(app, frame);
 
     if app.show_save_popup {
         app.save_popup.render(frame);
     }
 
+    if app.show_rename_popup {
+        app.rename_popup.render(frame);
+    }
+
+    if app.show_diff_popup {
+        app.diff_popup.render(frame);
+    }
+
+    if app.show_snippet_picker {
+        app.snippet_picker.render(frame);
+    }
+
+    if app.show_library {
+        app.library_picker.render(frame);
+    }
+
+    if app.show_library_save {
+        app.library_form.render(frame);
+    }
+
+    if app.show_params_popup {
+        app.params_form.render(frame);
+    }
+
+    if app.show_cell_inspector {
+        let popup_area = popup_area(frame.area(), 60, 60);
+        frame.render_widget(Clear, popup_area);
+        let title = format!(
+            "Cell {} [{}]",
+            app.tabs[active]
+                .table
+                .headers
+                .get(app.tabs[active].table_state.selected_col)
+                .map(String::as_str)
+                .unwrap_or_default(),
+            app.tabs[active].table_state.selected + 1
+        );
+        let inspector = Tree::new(&app.inspector)
+            .block(
+                Block::bordered()
+                    .title(title)
+                    .border_type(BorderType::Rounded)
+                    .border_style(Style::default().fg(AQUA).bg(BG)),
+            )
+            .style(Style::default().fg(FG).bg(BG))
+            .label_style(Style::default().fg(PURPLE).bold())
+            .detail_style(Style::default().fg(GRAY))
+            .highlight_style(Style::default().bg(GRAY));
+        frame.render_stateful_widget(inspector, popup_area, &mut app.inspector_state);
+    }
+
+    if app.show_help {
+        render_help(frame);
+    }
+
+    if let Some(preview) = &app.preview {
+        let popup_area = popup_area(frame.area(), 70, 60);
+        frame.render_widget(Clear, popup_area);
+        let (title, text, color) = match preview {
+            Ok(sql) => ("Query with variables substituted", sql.as_str(), FG),
+            Err(e) => ("Variables", e.as_str(), RED),
+        };
+        let preview = Paragraph::new(text)
+            .wrap(Wrap { trim: false })
+            .style(Style::default().fg(color).bg(BG))
+            .block(
+                Block::bordered()
+                    .title(title)
+                    .title_bottom("Alt+v or Esc to close")
+                    .border_type(BorderType::Rounded)
+                    .border_style(Style::default().fg(AQUA).bg(BG)),
+            );
+        frame.render_widget(preview, popup_area);
+    }
+
     if app.show_error_popup {
         let area = frame.area(); // Changed from f.size() to f.area()
-        let popup_area = popup_area(area, 60, 20);
+        let popup_area = popup_area(area, 70, 40);
         frame.render_widget(Clear, popup_area);
         let error_text = app.error.as_deref().unwrap_or("Unknown error");
-        let error_popup = Paragraph::new(Text::raw(error_text))
-            .block(Block::default().borders(Borders::ALL).title("Error"))
+        let mut lines = Vec::new();
+        match &app.diagnostic {
+            Some(diagnostic) => {
+                lines.push(Line::styled(
+                    diagnostic.kind.clone(),
+                    Style::default().fg(RED).bold(),
+                ));
+                lines.push(Line::raw(diagnostic.message.clone()));
+                lines.extend(
+                    diagnostic
+                        .details
+                        .iter()
+                        .map(|detail| Line::styled(detail.clone(), Style::default().fg(GRAY))),
+                );
+                if let Some((row, col)) = diagnostic.position {
+                    lines.push(Line::styled(
+                        format!("at line {}, column {}", row + 1, col + 1),
+                        Style::default().fg(YELLOW),
+                    ));
+                }
+                if !diagnostic.suggestions.is_empty() {
+                    lines.push(Line::raw(""));
+                    lines.push(Line::styled(
+                        "Fixes (Enter to apply):",
+                        Style::default().fg(AQUA),
+                    ));
+                    for (i, suggestion) in diagnostic.suggestions.iter().enumerate() {
+                        let style = if i == app.error_selected {
+                            Style::default().fg(BG).bg(AQUA)
+                        } else {
+                            Style::default().fg(FG)
+                        };
+                        lines.push(Line::styled(format!("  {suggestion}"), style));
+                    }
+                }
+            }
+            None => lines.push(Line::raw(error_text)),
+        }
+        let error_popup = Paragraph::new(lines)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Error")
+                    .title_bottom("↑/↓ select fix | PgUp/PgDn scroll | c copy | Esc close"),
+            )
             .wrap(Wrap { trim: true })
-            .style(Style::default().fg(Color::Red));
+            .scroll((app.error_scroll, 0))
+            .style(Style::default().fg(Color::Red).bg(BG));
         frame.render_widget(error_popup, popup_area);
     }
 }
@@ -0,0 +1,191 @@
+use std::time::Duration;
+
+use duckdb::Connection;
+
+use crate::diagnostic::Diagnostic;
+
+/// Time without edits after which the statement under the cursor is validated.
+pub const IDLE: Duration = Duration::from_millis(750);
+
+/// Statements that are never prepared while typing, because they change the
+/// session or the transaction, or load code.
+const SKIPPED: &[&str] = &[
+    "ABORT",
+    "ATTACH",
+    "BEGIN",
+    "CALL",
+    "CHECKPOINT",
+    "COMMIT",
+    "DETACH",
+    "END",
+    "EXPORT",
+    "FORCE",
+    "IMPORT",
+    "INSTALL",
+    "LOAD",
+    "PRAGMA",
+    "RESET",
+    "ROLLBACK",
+    "SET",
+    "START",
+    "USE",
+    "VACUUM",
+];
+
+/// A statement of the buffer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Statement {
+    /// Text of the statement, without the `;` that ends it.
+    pub text: String,
+    /// Row and column in the buffer where the text starts.
+    pub start: (usize, usize),
+}
+
+/// Problem found in the statement under the cursor.
+#[derive(Debug, Clone)]
+pub struct Validation {
+    /// The error, with its position in buffer coordinates.
+    pub diagnostic: Diagnostic,
+    /// Row of the gutter sign: the error position, or the start of the statement.
+    pub row: usize,
+}
+
+/// Splits `text` into statements at the `;` that are not quoted or in comments.
+pub fn statements(text: &str) -> Vec<Statement> {
+    #[derive(PartialEq)]
+    enum State {
+        Code,
+        Quoted(char),
+        LineComment,
+        BlockComment,
+    }
+
+    let mut statements = Vec::new();
+    let mut current = Statement {
+        text: String::new(),
+        start: (0, 0),
+    };
+    let mut state = State::Code;
+    let (mut row, mut col) = (0, 0);
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        let next = chars.peek().copied();
+        match state {
+            State::Code if c == ';' => {
+                statements.push(std::mem::replace(
+                    &mut current,
+                    Statement {
+                        text: String::new(),
+                        start: (row, col + 1),
+                    },
+                ));
+                col += 1;
+                continue;
+            }
+            State::Code if c == '\'' || c == '"' => state = State::Quoted(c),
+            State::Code if c == '-' && next == Some('-') => state = State::LineComment,
+            State::Code if c == '/' && next == Some('*') => state = State::BlockComment,
+            State::Quoted(quote) if c == quote => state = State::Code,
+            State::LineComment if c == '\n' => state = State::Code,
+            State::BlockComment if c == '*' && next == Some('/') => {
+                current.text.push(c);
+                current.text.extend(chars.next());
+                col += 2;
+                state = State::Code;
+                continue;
+            }
+            _ => {}
+        }
+        current.text.push(c);
+        if c == '\n' {
+            row += 1;
+            col = 0;
+        } else {
+            col += 1;
+        }
+    }
+    statements.push(current);
+    statements
+}
+
+/// Returns the statement under `cursor`.
+///
+/// A cursor right after a `;` is on the statement the `;` ends. On a blank
+/// statement, such as after the last `;`, the one before it is used.
+pub fn statement_at(text: &str, cursor: (usize, usize)) -> Option<Statement> {
+    let statements = statements(text);
+    let index = statements
+        .iter()
+        .rposition(|statement| statement.start < cursor)
+        .unwrap_or(0);
+    statements[..=index]
+        .iter()
+        .rev()
+        .find(|statement| !statement.text.trim().is_empty())
+        .cloned()
+}
+
+/// Returns the first keyword of `statement` in upper case, after any comments.
+pub fn keyword(statement: &str) -> String {
+    let mut rest = statement.trim_start();
+    // Skip leading comments to find the first keyword.
+    loop {
+        if let Some(comment) = rest.strip_prefix("--") {
+            rest = comment.split_once('\n').map_or("", |(_, rest)| rest);
+        } else if let Some(comment) = rest.strip_prefix("/*") {
+            rest = comment.split_once("*/").map_or("", |(_, rest)| rest);
+        } else {
+            break;
+        }
+        rest = rest.trim_start();
+    }
+    rest.chars()
+        .take_while(|c| c.is_alphabetic())
+        .collect::<String>()
+        .to_ascii_uppercase()
+}
+
+/// Whether `statement` can be checked without effects.
+fn is_checked(statement: &str) -> bool {
+    // DuckDB runs all but the last of several statements when preparing them,
+    // so anything that might hold more than one is left alone.
+    if statement.contains(';') {
+        return false;
+    }
+    // Binding reads the start of remote files, such as `'s3://bucket/data.csv'`,
+    // which would block typing for a network round trip.
+    if statement.contains("://") {
+        return false;
+    }
+    let keyword = keyword(statement);
+    !keyword.is_empty() && !SKIPPED.contains(&keyword.as_str())
+}
+
+/// Parses and binds `statement` without running it.
+///
+/// Returns `None` if the statement is valid or is not checked.
+pub fn validate(connection: &Connection, statement: &Statement) -> Option<Validation> {
+    if !is_checked(&statement.text) {
+        return None;
+    }
+    let error = connection.prepare(&statement.text).err()?;
+    let mut diagnostic = Diagnostic::parse(&format!("Error: {}", error), &statement.text);
+    diagnostic.position = diagnostic.position.map(|(row, col)| {
+        let (start_row, start_col) = statement.start;
+        if row == 0 {
+            (start_row, start_col + col)
+        } else {
+            (start_row + row, col)
+        }
+    });
+    // Point the gutter at the first line with code when the error has no position.
+    let first_code = statement
+        .text
+        .lines()
+        .position(|line| !line.trim().is_empty())
+        .unwrap_or(0);
+    let row = diagnostic
+        .position
+        .map_or(statement.start.0 + first_code, |(row, _)| row);
+    Some(Validation { diagnostic, row })
+}
@@ -0,0 +1,137 @@
+use std::ops::Range;
+
+/// Returns the length and name of the `${NAME}` variable that `text` starts with.
+fn variable_at(text: &str) -> Option<(usize, &str)> {
+    let rest = text.strip_prefix("${")?;
+    let length = rest
+        .char_indices()
+        .take_while(|(i, c)| c.is_ascii_alphabetic() || *c == '_' || (*i > 0 && c.is_ascii_digit()))
+        .count();
+    let name = &rest[..length];
+    (!name.is_empty() && rest[length..].starts_with('}')).then_some((length + 3, name))
+}
+
+/// Whether `name` can be used as a `${NAME}` variable.
+pub fn is_name(name: &str) -> bool {
+    variable_at(&format!("${{{name}}}")).is_some()
+}
+
+/// A `${NAME}` variable, or `$${NAME}` escape, replaced by [`substitute_mapped`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Replacement {
+    /// Bytes of the variable in the original text.
+    pub original: Range<usize>,
+    /// Bytes of its value in the substituted text.
+    pub substituted: Range<usize>,
+}
+
+/// Maps a byte offset in substituted text back to the original text.
+///
+/// Offsets inside a value map to the start of its variable.
+pub fn original_offset(replacements: &[Replacement], offset: usize) -> usize {
+    let mut shift = 0;
+    for replacement in replacements {
+        if offset < replacement.substituted.start {
+            break;
+        }
+        if offset < replacement.substituted.end {
+            return replacement.original.start;
+        }
+        shift = replacement.original.end as isize - replacement.substituted.end as isize;
+    }
+    offset.saturating_add_signed(shift)
+}
+
+/// Replaces the `${NAME}` variables in `sql` with their values.
+///
+/// `$${NAME}` is a literal `${NAME}`, and variables in comments are left
+/// alone. Fails with the names of the variables `value` does not know.
+pub fn substitute(sql: &str, value: impl Fn(&str) -> Option<String>) -> Result<String, String> {
+    substitute_mapped(sql, value).map(|(substituted, _)| substituted)
+}
+
+/// Like [`substitute`], also returning where each variable was replaced, in order.
+pub fn substitute_mapped(
+    sql: &str,
+    value: impl Fn(&str) -> Option<String>,
+) -> Result<(String, Vec<Replacement>), String> {
+    #[derive(PartialEq)]
+    enum State {
+        Code,
+        Quoted(char),
+        LineComment,
+        BlockComment,
+    }
+
+    let mut substituted = String::with_capacity(sql.len());
+    let mut replacements = Vec::new();
+    let mut undefined: Vec<&str> = Vec::new();
+    let mut state = State::Code;
+    let mut i = 0;
+    while let Some(c) = sql[i..].chars().next() {
+        let rest = &sql[i..];
+        match state {
+            State::LineComment if c == '\n' => state = State::Code,
+            State::BlockComment if rest.starts_with("*/") => {
+                substituted.push_str("*/");
+                i += 2;
+                state = State::Code;
+                continue;
+            }
+            State::LineComment | State::BlockComment => {}
+            State::Code | State::Quoted(_) => {
+                if let Some((length, _)) = rest.strip_prefix('$').and_then(variable_at) {
+                    let start = substituted.len();
+                    substituted.push_str(&rest[1..=length]);
+                    replacements.push(Replacement {
+                        original: i..i + length + 1,
+                        substituted: start..substituted.len(),
+                    });
+                    i += length + 1;
+                    continue;
+                }
+                if let Some((length, name)) = variable_at(rest) {
+                    match value(name) {
+                        Some(value) => {
+                            let start = substituted.len();
+                            substituted.push_str(&value);
+                            replacements.push(Replacement {
+                                original: i..i + length,
+                                substituted: start..substituted.len(),
+                            });
+                        }
+                        None => {
+                            if !undefined.contains(&name) {
+                                undefined.push(name);
+                            }
+                            substituted.push_str(&rest[..length]);
+                        }
+                    }
+                    i += length;
+                    continue;
+                }
+                match (&state, c) {
+                    (State::Code, '\'' | '"') => state = State::Quoted(c),
+                    (State::Quoted(quote), c) if c == *quote => state = State::Code,
+                    (State::Code, '-') if rest.starts_with("--") => state = State::LineComment,
+                    (State::Code, '/') if rest.starts_with("/*") => state = State::BlockComment,
+                    _ => {}
+                }
+            }
+        }
+        substituted.push(c);
+        i += c.len_utf8();
+    }
+
+    match undefined.as_slice() {
+        [] => Ok((substituted, replacements)),
+        names => {
+            let names: Vec<String> = names.iter().map(|name| format!("${{{name}}}")).collect();
+            let plural = if names.len() > 1 { "s" } else { "" };
+            Err(format!(
+                "Undefined variable{plural} {}: set with --var NAME=value, vars.NAME in the config file or the environment",
+                names.join(", ")
+            ))
+        }
+    }
+}